@@ -23,12 +23,11 @@ SOFTWARE.
 */
 //! Module for [`TtfbError`].
 
+use crate::GrpcError;
 use derive_more::Display;
 use hickory_resolver::{ResolveError, ResolveErrorKind};
-use rustls_connector::HandshakeError;
 use std::error::Error;
 use std::io;
-use std::net::TcpStream;
 
 /// Errors during DNS resolving.
 #[derive(Clone, Debug, Display)]
@@ -80,9 +79,24 @@ pub enum InvalidUrlError {
     /// The URL is illegal.
     #[display("The URL is illegal because: {}", _0)]
     WrongFormat(String),
-    /// Wrong scheme. Only supports http and https.
-    #[display("Wrong scheme '{}://': Only supports http and https.", _0)]
+    /// Wrong scheme. Only supports http, https, ws, wss, tcp, tls, smtp, imap,
+    /// pop3, postgres, grpc, and grpcs.
+    #[display(
+        "Wrong scheme '{}://': Only supports http, https, ws, wss, tcp, tls, smtp, imap, pop3, postgres, grpc, and grpcs.",
+        _0
+    )]
     WrongScheme(String),
+    /// The scheme, such as `tcp`, is measured without HTTP by
+    /// [`probe`](crate::probe()) instead of [`ttfb`](crate::ttfb).
+    #[display("The scheme '{}://' is measured without HTTP by probe()", _0)]
+    ProbeScheme(String),
+    /// The scheme, such as `https`, is measured with HTTP by
+    /// [`ttfb`](crate::ttfb) instead of [`probe`](crate::probe()).
+    #[display("The scheme '{}://' is measured with HTTP by ttfb()", _0)]
+    HttpScheme(String),
+    /// The URL has no port, and the scheme has no default port.
+    #[display("The URL must contain a port, such as tcp://example.com:5432")]
+    MissingPort,
     /// Other unknown error.
     #[display("Other unknown error.")]
     Other,
@@ -104,13 +118,34 @@ pub enum TtfbError {
     CantConnectTcp(io::Error),
     /// Can't establish TLS-Connection.
     #[display("Can't establish TLS-Connection because: {}", _0)]
-    CantConnectTls(Box<HandshakeError<TcpStream>>),
+    CantConnectTls(io::Error),
+    /// The TLS options are invalid, e.g., an unknown cipher suite was
+    /// requested.
+    #[display("Invalid TLS options: {}", _0)]
+    InvalidTlsOptions(String),
+    /// The HTTP options are invalid, e.g., a header value contains a line
+    /// break.
+    #[display("Invalid HTTP options: {}", _0)]
+    InvalidHttpOptions(String),
     /// Can't verify TLS-Connection.
     #[display("Can't verify TLS-Connection because: {}", _0)]
-    CantVerifyTls(Box<HandshakeError<TcpStream>>),
+    CantVerifyTls(io::Error),
+    /// The URL requires TLS, but this crate was built without a TLS backend.
+    #[display("Can't use TLS, as ttfb was built without a TLS backend")]
+    NoTlsBackend,
     /// Can't establish HTTP/1.1-Connection.
     #[display("Can't establish HTTP/1.1-Connection because: {}", _0)]
     CantConnectHttp(io::Error),
+    /// The response of the server isn't a valid HTTP/1.1 response.
+    #[display("Invalid HTTP response: {}", _0)]
+    InvalidHttpResponse(String),
+    /// The server rejected STARTTLS or responded unexpectedly during the
+    /// negotiation.
+    #[display("STARTTLS negotiation failed: {}", _0)]
+    StartTlsFailed(String),
+    /// The gRPC health check of `grpc://` and `grpcs://` URLs failed.
+    #[display("gRPC health check failed: {}", _0)]
+    Grpc(GrpcError),
     /// Didn't receive any data after sending the HTTP GET request.
     #[display("Didn't receive any data. Is the host running a HTTP server?")]
     NoHttpResponse,
@@ -122,6 +157,31 @@ pub enum TtfbError {
     CantConfigureDNSError(ResolveError),
 }
 
+impl TtfbError {
+    /// Returns the name of the variant, such as `CantConnectTcp`, e.g., to
+    /// report the error in a structured format together with its message.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidUrl(_) => "InvalidUrl",
+            Self::CantResolveDns(_) => "CantResolveDns",
+            Self::CantConnectTcp(_) => "CantConnectTcp",
+            Self::CantConnectTls(_) => "CantConnectTls",
+            Self::InvalidTlsOptions(_) => "InvalidTlsOptions",
+            Self::InvalidHttpOptions(_) => "InvalidHttpOptions",
+            Self::CantVerifyTls(_) => "CantVerifyTls",
+            Self::NoTlsBackend => "NoTlsBackend",
+            Self::CantConnectHttp(_) => "CantConnectHttp",
+            Self::InvalidHttpResponse(_) => "InvalidHttpResponse",
+            Self::StartTlsFailed(_) => "StartTlsFailed",
+            Self::Grpc(_) => "Grpc",
+            Self::NoHttpResponse => "NoHttpResponse",
+            Self::OtherStreamError(_) => "OtherStreamError",
+            Self::CantConfigureDNSError(_) => "CantConfigureDNSError",
+        }
+    }
+}
+
 impl Error for TtfbError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -132,6 +192,12 @@ impl Error for TtfbError {
             Self::OtherStreamError(err) => Some(err),
             Self::CantConnectHttp(err) => Some(err),
             Self::NoHttpResponse => None,
+            Self::InvalidTlsOptions(_) => None,
+            Self::InvalidHttpOptions(_) => None,
+            Self::InvalidHttpResponse(_) => None,
+            Self::StartTlsFailed(_) => None,
+            Self::Grpc(err) => Some(err),
+            Self::NoTlsBackend => None,
             Self::CantConfigureDNSError(err) => Some(err),
             Self::CantVerifyTls(err) => Some(err),
         }
@@ -161,7 +227,64 @@ impl PartialEq for TtfbError {
                 true
             }
             (Self::NoHttpResponse, Self::NoHttpResponse) => true,
+            (Self::InvalidTlsOptions(e1), Self::InvalidTlsOptions(e2)) => e1.eq(e2),
+            (Self::InvalidHttpOptions(e1), Self::InvalidHttpOptions(e2)) => e1.eq(e2),
+            (Self::InvalidHttpResponse(e1), Self::InvalidHttpResponse(e2)) => e1.eq(e2),
+            (Self::StartTlsFailed(e1), Self::StartTlsFailed(e2)) => e1.eq(e2),
+            (Self::Grpc(e1), Self::Grpc(e2)) => e1.eq(e2),
+            (Self::NoTlsBackend, Self::NoTlsBackend) => true,
             _ => false,
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TtfbError {
+    /// Serializes the error as [`TtfbErrorInfo`], as the underlying errors,
+    /// such as [`io::Error`], aren't serializable.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TtfbErrorInfo::from(self).serialize(serializer)
+    }
+}
+
+/// Representation of a [`TtfbError`] by the name of its variant and its
+/// message.
+///
+/// Unlike [`TtfbError`], it can be cloned and, with the `serde` feature,
+/// deserialized, e.g., to persist failed measurements together with the
+/// successful ones.
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[display("{message}")]
+pub struct TtfbErrorInfo {
+    /// See [`TtfbError::kind`].
+    kind: String,
+    /// The message of the error.
+    message: String,
+}
+
+impl TtfbErrorInfo {
+    /// Returns the name of the variant of the [`TtfbError`], such as
+    /// `CantConnectTcp`.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Returns the message of the error.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<&TtfbError> for TtfbErrorInfo {
+    fn from(err: &TtfbError) -> Self {
+        Self {
+            kind: err.kind().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
@@ -0,0 +1,593 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the gRPC health check (`grpc.health.v1.Health/Check`) of
+//! `grpc://` and `grpcs://` URLs, which is done over HTTP/2.
+
+use crate::request::host_header;
+use crate::{CRATE_VERSION, IoReadAndWrite, Scheme, TtfbError};
+use derive_more::Display;
+use percent_encoding::percent_decode_str;
+use std::error::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Connection preface of HTTP/2 clients.
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Path of the method of the gRPC health checking protocol.
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+/// Stream of the call, i.e., the first stream of the client.
+const STREAM_ID: u32 = 1;
+
+/// Maximum size of a frame that is accepted, which is the limit of HTTP/2.
+const MAX_FRAME_SIZE: usize = (1 << 24) - 1;
+
+/// Initial flow-control window of the connection and the stream, which is
+/// the default of HTTP/2, as the client doesn't change it.
+const INITIAL_WINDOW_SIZE: u32 = 65_535;
+
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_RST_STREAM: u8 = 0x3;
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_PING: u8 = 0x6;
+const FRAME_GOAWAY: u8 = 0x7;
+const FRAME_WINDOW_UPDATE: u8 = 0x8;
+const FRAME_CONTINUATION: u8 = 0x9;
+
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+/// Serving status of a `grpc.health.v1.HealthCheckResponse`.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+pub enum GrpcServingStatus {
+    /// `UNKNOWN`
+    #[display("UNKNOWN")]
+    Unknown,
+    /// `SERVING`
+    #[display("SERVING")]
+    Serving,
+    /// `NOT_SERVING`
+    #[display("NOT_SERVING")]
+    NotServing,
+    /// `SERVICE_UNKNOWN`
+    #[display("SERVICE_UNKNOWN")]
+    ServiceUnknown,
+    /// A status that is unknown to this crate.
+    #[display("{_0}")]
+    Other(u64),
+}
+
+impl From<u64> for GrpcServingStatus {
+    fn from(value: u64) -> Self {
+        match value {
+            0 => Self::Unknown,
+            1 => Self::Serving,
+            2 => Self::NotServing,
+            3 => Self::ServiceUnknown,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Reasons why the gRPC health check of `grpc://` and `grpcs://` URLs failed.
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
+pub enum GrpcError {
+    /// The health check succeeded, but the serving status isn't `SERVING`.
+    #[display("The serving status is {_0}")]
+    NotServing(GrpcServingStatus),
+    /// The call failed with a `grpc-status` other than `0` (`OK`), such as
+    /// `5` (`NOT_FOUND`) for an unknown service, or `12` (`UNIMPLEMENTED`) if
+    /// the server doesn't implement the health checking protocol.
+    #[display("The call failed with grpc-status {code}: {message}")]
+    Status {
+        /// The `grpc-status`.
+        code: u32,
+        /// The `grpc-message`, if any.
+        message: String,
+    },
+    /// The response isn't a valid HTTP/2 or gRPC response.
+    #[display("Invalid response: {_0}")]
+    InvalidResponse(String),
+    /// The server of a `grpcs://` URL didn't select HTTP/2 (`h2`) via ALPN,
+    /// which gRPC requires.
+    #[display("The server didn't select HTTP/2 (h2) via ALPN")]
+    NoHttp2,
+}
+
+impl Error for GrpcError {}
+
+/// Measurements of [`grpc_health_check`].
+pub struct GrpcExchange {
+    /// Duration of sending the connection preface and the request.
+    pub send_duration: Duration,
+    /// Duration between sending the request and the response headers.
+    pub headers_duration: Duration,
+    /// The `:status` of the response headers.
+    pub status: u16,
+    /// Duration between the response headers and the trailers.
+    pub trailers_duration: Duration,
+}
+
+/// Performs the health check of the service in the path of the URL (the
+/// whole server, if the path is empty) via HTTP/2. For `grpcs://`, the
+/// server must have selected `h2` via ALPN, which is given as
+/// `alpn_protocol`. For `grpc://`, HTTP/2 is used with prior knowledge.
+/// Fails with [`GrpcError`], if the serving status isn't `SERVING`.
+pub fn grpc_health_check(
+    tcp: &mut Box<dyn IoReadAndWrite>,
+    url: &Url,
+    alpn_protocol: Option<&str>,
+) -> Result<GrpcExchange, TtfbError> {
+    let scheme = if Scheme::from_name(url.scheme()) == Some(Scheme::Grpcs) {
+        if alpn_protocol != Some("h2") {
+            return Err(TtfbError::Grpc(GrpcError::NoHttp2));
+        }
+        "https"
+    } else {
+        "http"
+    };
+    let headers = [
+        (":method", "POST".to_string()),
+        (":scheme", scheme.to_string()),
+        (":path", HEALTH_CHECK_PATH.to_string()),
+        (":authority", host_header(url)),
+        ("content-type", "application/grpc".to_string()),
+        ("te", "trailers".to_string()),
+        ("user-agent", format!("ttfb/{CRATE_VERSION}")),
+    ];
+    let service = url.path().trim_start_matches('/');
+
+    let mut request = PREFACE.to_vec();
+    // Empty, i.e., the defaults.
+    write_frame(&mut request, FRAME_SETTINGS, 0, 0, &[]);
+    write_frame(
+        &mut request,
+        FRAME_HEADERS,
+        FLAG_END_HEADERS,
+        STREAM_ID,
+        &encode_headers(&headers),
+    );
+    write_frame(
+        &mut request,
+        FRAME_DATA,
+        FLAG_END_STREAM,
+        STREAM_ID,
+        &health_check_request(service),
+    );
+    let now = Instant::now();
+    tcp.write_all(&request)
+        .and_then(|()| tcp.flush())
+        .map_err(TtfbError::CantConnectHttp)?;
+    let send_duration = now.elapsed();
+
+    let now = Instant::now();
+    let mut decoder = loona_hpack::Decoder::new();
+    let mut response_headers = None;
+    let mut data = Vec::new();
+    // Received, but not yet acknowledged, bytes of the flow-control windows.
+    let mut unacknowledged = 0;
+    let trailers = loop {
+        let (frame_type, flags, stream_id, payload) = read_frame(tcp)?;
+        match frame_type {
+            FRAME_SETTINGS if flags & FLAG_ACK == 0 => {
+                let mut ack = Vec::new();
+                write_frame(&mut ack, FRAME_SETTINGS, FLAG_ACK, 0, &[]);
+                write(tcp, &ack)?;
+            }
+            FRAME_PING if flags & FLAG_ACK == 0 => {
+                let mut ack = Vec::new();
+                write_frame(&mut ack, FRAME_PING, FLAG_ACK, 0, &payload);
+                write(tcp, &ack)?;
+            }
+            FRAME_GOAWAY => {
+                return Err(invalid_response(format!(
+                    "The server sent GOAWAY with error code {}",
+                    error_code(payload.get(4..).unwrap_or_default())
+                )));
+            }
+            FRAME_RST_STREAM if stream_id == STREAM_ID => {
+                return Err(invalid_response(format!(
+                    "The server reset the stream with error code {}",
+                    error_code(&payload)
+                )));
+            }
+            FRAME_HEADERS if stream_id == STREAM_ID => {
+                let arrival = now.elapsed();
+                let block = read_header_block(tcp, flags, &payload)?;
+                let headers = decoder
+                    .decode(&block)
+                    .map_err(|err| invalid_response(format!("Invalid header block: {err}")))?;
+                if flags & FLAG_END_STREAM != 0 {
+                    break (arrival, headers);
+                }
+                response_headers.get_or_insert((arrival, headers));
+            }
+            FRAME_DATA if stream_id == STREAM_ID => {
+                data.extend_from_slice(strip_padding(flags, &payload)?);
+                if flags & FLAG_END_STREAM != 0 {
+                    return Err(invalid_response("The stream ended without trailers"));
+                }
+                // The padding counts towards the windows, too. Like other
+                // clients, replenish them once half of them is used, so that
+                // large responses don't stall.
+                unacknowledged += payload.len() as u32;
+                if unacknowledged >= INITIAL_WINDOW_SIZE / 2 {
+                    let increment = unacknowledged.to_be_bytes();
+                    let mut update = Vec::new();
+                    write_frame(&mut update, FRAME_WINDOW_UPDATE, 0, 0, &increment);
+                    write_frame(&mut update, FRAME_WINDOW_UPDATE, 0, STREAM_ID, &increment);
+                    write(tcp, &update)?;
+                    unacknowledged = 0;
+                }
+            }
+            _ => {}
+        }
+    };
+    // Without a message, the trailers are sent as the only headers.
+    let (headers_duration, headers) = response_headers.unwrap_or_else(|| trailers.clone());
+    let (trailers_arrival, trailers) = trailers;
+
+    let status = header(&headers, ":status")
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid_response("Missing :status"))?;
+    if status != 200 {
+        return Err(invalid_response(format!("HTTP status {status}")));
+    }
+    let code = header(&trailers, "grpc-status")
+        .and_then(|code| code.parse::<u32>().ok())
+        .ok_or_else(|| invalid_response("Missing grpc-status"))?;
+    if code != 0 {
+        let message = header(&trailers, "grpc-message").unwrap_or_default();
+        return Err(TtfbError::Grpc(GrpcError::Status {
+            code,
+            message: percent_decode_str(&message)
+                .decode_utf8_lossy()
+                .into_owned(),
+        }));
+    }
+    match serving_status(&data)? {
+        GrpcServingStatus::Serving => Ok(GrpcExchange {
+            send_duration,
+            headers_duration,
+            status,
+            trailers_duration: trailers_arrival - headers_duration,
+        }),
+        serving_status => Err(TtfbError::Grpc(GrpcError::NotServing(serving_status))),
+    }
+}
+
+/// Encodes the headers as header block, without Huffman coding.
+fn encode_headers<T: AsRef<[u8]>>(headers: &[(&str, T)]) -> Vec<u8> {
+    loona_hpack::Encoder::new().encode(
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_bytes(), value.as_ref())),
+    )
+}
+
+fn invalid_response(message: impl Into<String>) -> TtfbError {
+    TtfbError::Grpc(GrpcError::InvalidResponse(message.into()))
+}
+
+/// Returns the error code of `RST_STREAM` and `GOAWAY` frames.
+fn error_code(payload: &[u8]) -> u32 {
+    payload
+        .get(..4)
+        .map_or(0, |code| u32::from_be_bytes(code.try_into().unwrap()))
+}
+
+/// Returns the value of the header of the given (lowercase) name.
+fn header(headers: &[(Vec<u8>, Vec<u8>)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name.as_bytes())
+        .map(|(_, value)| String::from_utf8_lossy(value).into_owned())
+}
+
+fn write(tcp: &mut Box<dyn IoReadAndWrite>, data: &[u8]) -> Result<(), TtfbError> {
+    tcp.write_all(data)
+        .and_then(|()| tcp.flush())
+        .map_err(TtfbError::OtherStreamError)
+}
+
+/// Appends a frame to the buffer.
+fn write_frame(buffer: &mut Vec<u8>, frame_type: u8, flags: u8, stream_id: u32, payload: &[u8]) {
+    buffer.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    buffer.push(frame_type);
+    buffer.push(flags);
+    buffer.extend_from_slice(&stream_id.to_be_bytes());
+    buffer.extend_from_slice(payload);
+}
+
+/// Reads a frame and returns its type, flags, stream, and payload.
+fn read_frame(reader: &mut impl Read) -> Result<(u8, u8, u32, Vec<u8>), TtfbError> {
+    let map_err = |err: std::io::Error| {
+        if err.kind() == ErrorKind::UnexpectedEof {
+            invalid_response("The server closed the connection")
+        } else {
+            TtfbError::OtherStreamError(err)
+        }
+    };
+    let mut header = [0_u8; 9];
+    reader.read_exact(&mut header).map_err(map_err)?;
+    if header.starts_with(b"HTTP/") {
+        return Err(invalid_response(
+            "The server responded with HTTP/1.x instead of HTTP/2",
+        ));
+    }
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(invalid_response("The frame is too large"));
+    }
+    let stream_id = u32::from_be_bytes(header[5..].try_into().unwrap()) & 0x7fff_ffff;
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).map_err(map_err)?;
+    Ok((header[3], header[4], stream_id, payload))
+}
+
+/// Returns the payload of a `DATA` or `HEADERS` frame without the padding.
+fn strip_padding(flags: u8, payload: &[u8]) -> Result<&[u8], TtfbError> {
+    if flags & FLAG_PADDED == 0 {
+        return Ok(payload);
+    }
+    let padding = usize::from(*payload.first().unwrap_or(&0));
+    payload
+        .get(1..payload.len().saturating_sub(padding))
+        .filter(|_| payload.len() > padding)
+        .ok_or_else(|| invalid_response("Invalid padding"))
+}
+
+/// Returns the complete header block of a `HEADERS` frame, including the
+/// `CONTINUATION` frames that follow it.
+fn read_header_block(
+    reader: &mut impl Read,
+    flags: u8,
+    payload: &[u8],
+) -> Result<Vec<u8>, TtfbError> {
+    let mut fragment = strip_padding(flags, payload)?;
+    if flags & FLAG_PRIORITY != 0 {
+        fragment = fragment
+            .get(5..)
+            .ok_or_else(|| invalid_response("Invalid priority"))?;
+    }
+    let mut block = fragment.to_vec();
+    let mut end_headers = flags & FLAG_END_HEADERS != 0;
+    while !end_headers {
+        let (frame_type, flags, stream_id, payload) = read_frame(reader)?;
+        if frame_type != FRAME_CONTINUATION || stream_id != STREAM_ID {
+            return Err(invalid_response("Expected a CONTINUATION frame"));
+        }
+        block.extend_from_slice(&payload);
+        end_headers = flags & FLAG_END_HEADERS != 0;
+    }
+    Ok(block)
+}
+
+/// Returns the gRPC message (uncompressed) with a `HealthCheckRequest` for
+/// the service.
+fn health_check_request(service: &str) -> Vec<u8> {
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        // Field 1 (service), length-delimited
+        message.push(0x0a);
+        write_varint(&mut message, service.len() as u64);
+        message.extend_from_slice(service.as_bytes());
+    }
+    let mut grpc_message = vec![0];
+    grpc_message.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    grpc_message.extend(message);
+    grpc_message
+}
+
+/// Returns the serving status of the `HealthCheckResponse` in the gRPC
+/// message.
+fn serving_status(data: &[u8]) -> Result<GrpcServingStatus, TtfbError> {
+    let invalid = || invalid_response("Invalid HealthCheckResponse");
+    let (prefix, message) = data.split_at_checked(5).ok_or_else(invalid)?;
+    if prefix[0] != 0 {
+        return Err(invalid_response("Compressed messages aren't supported"));
+    }
+    let len = u32::from_be_bytes(prefix[1..].try_into().unwrap()) as usize;
+    let mut message = message.get(..len).ok_or_else(invalid)?;
+    // Default of proto3, if the field is missing.
+    let mut status = 0;
+    while !message.is_empty() {
+        let tag = read_varint(&mut message).ok_or_else(invalid)?;
+        match (tag >> 3, tag & 0x7) {
+            // Field 1 (status), varint
+            (1, 0) => status = read_varint(&mut message).ok_or_else(invalid)?,
+            (_, 0) => {
+                read_varint(&mut message).ok_or_else(invalid)?;
+            }
+            (_, wire_type @ (1 | 2 | 5)) => {
+                let len = match wire_type {
+                    1 => 8,
+                    5 => 4,
+                    _ => read_varint(&mut message).ok_or_else(invalid)? as usize,
+                };
+                message = message.get(len..).ok_or_else(invalid)?;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(GrpcServingStatus::from(status))
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Reads a varint and advances the slice.
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn health_check_messages() {
+        assert_eq!(health_check_request(""), [0, 0, 0, 0, 0]);
+        assert_eq!(
+            health_check_request("foo"),
+            [0, 0, 0, 0, 5, 0x0a, 3, b'f', b'o', b'o']
+        );
+        assert_eq!(
+            serving_status(&[0, 0, 0, 0, 2, 0x08, 1]).unwrap(),
+            GrpcServingStatus::Serving
+        );
+        // Unknown fields are skipped, and a missing status is UNKNOWN.
+        assert_eq!(
+            serving_status(&[0, 0, 0, 0, 3, 0x12, 1, 0]).unwrap(),
+            GrpcServingStatus::Unknown
+        );
+        assert!(serving_status(&[0, 0, 0, 0, 2, 0x08]).is_err());
+    }
+
+    /// Runs an emulated gRPC server that responds with the serving status
+    /// and the `grpc-status`, and performs the health check with it.
+    fn health_check_with(
+        serving_status: u8,
+        grpc_status: &'static str,
+    ) -> Result<GrpcExchange, TtfbError> {
+        let mut response = Vec::new();
+        write_frame(&mut response, FRAME_SETTINGS, 0, 0, &[]);
+        let headers = encode_headers(&[(":status", "200"), ("content-type", "application/grpc")]);
+        write_frame(&mut response, FRAME_HEADERS, FLAG_END_HEADERS, 1, &headers);
+        let message = [0, 0, 0, 0, 2, 0x08, serving_status];
+        write_frame(&mut response, FRAME_DATA, 0, 1, &message);
+        let trailers = encode_headers(&[
+            ("grpc-status", grpc_status),
+            ("grpc-message", "not%20found"),
+        ]);
+        let flags = FLAG_END_HEADERS | FLAG_END_STREAM;
+        write_frame(&mut response, FRAME_HEADERS, flags, 1, &trailers);
+        health_check_with_response(response)
+    }
+
+    /// Runs an emulated gRPC server that checks the request and responds
+    /// with the given frames, and performs the health check with it.
+    fn health_check_with_response(response: Vec<u8>) -> Result<GrpcExchange, TtfbError> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut preface = [0; PREFACE.len()];
+            stream.read_exact(&mut preface).unwrap();
+            assert_eq!(preface, PREFACE);
+            let mut decoder = loona_hpack::Decoder::new();
+            loop {
+                let (frame_type, flags, _, payload) = read_frame(&mut stream).unwrap();
+                if frame_type == FRAME_HEADERS {
+                    let headers = decoder.decode(&payload).unwrap();
+                    assert_eq!(header(&headers, ":path").unwrap(), HEALTH_CHECK_PATH);
+                    assert_eq!(header(&headers, ":authority").unwrap(), addr.to_string());
+                }
+                if frame_type == FRAME_DATA {
+                    assert_eq!(payload, health_check_request("my.Service"));
+                }
+                if flags & FLAG_END_STREAM != 0 && frame_type != FRAME_SETTINGS {
+                    break;
+                }
+            }
+
+            stream.write_all(&response).unwrap();
+            // Wait for the SETTINGS ACK.
+            read_frame(&mut stream).unwrap();
+        });
+
+        let url = Url::parse(&format!("grpc://{addr}/my.Service")).unwrap();
+        let mut tcp: Box<dyn IoReadAndWrite> = Box::new(TcpStream::connect(addr).unwrap());
+        let result = grpc_health_check(&mut tcp, &url, None);
+        server.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn grpc_health_check_with_emulated_server() {
+        let exchange = health_check_with(1, "0").unwrap();
+        assert_eq!(exchange.status, 200);
+
+        assert_eq!(
+            health_check_with(2, "0").err(),
+            Some(TtfbError::Grpc(GrpcError::NotServing(
+                GrpcServingStatus::NotServing
+            )))
+        );
+        assert_eq!(
+            health_check_with(0, "5").err(),
+            Some(TtfbError::Grpc(GrpcError::Status {
+                code: 5,
+                message: "not found".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn grpc_health_check_with_malformed_header_block() {
+        let mut response = Vec::new();
+        write_frame(&mut response, FRAME_SETTINGS, 0, 0, &[]);
+        // A dynamic table size update whose integer is truncated.
+        write_frame(&mut response, FRAME_HEADERS, FLAG_END_HEADERS, 1, &[0x3f]);
+        let result = health_check_with_response(response);
+        assert!(
+            matches!(result, Err(TtfbError::Grpc(GrpcError::InvalidResponse(_)))),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    #[test]
+    fn grpcs_requires_h2() {
+        let url = Url::parse("grpcs://127.0.0.1:1/").unwrap();
+        let mut tcp: Box<dyn IoReadAndWrite> = Box::new(std::io::Cursor::new(Vec::new()));
+        for alpn_protocol in [None, Some("http/1.1")] {
+            assert_eq!(
+                grpc_health_check(&mut tcp, &url, alpn_protocol).err(),
+                Some(TtfbError::Grpc(GrpcError::NoHttp2))
+            );
+        }
+    }
+}
@@ -0,0 +1,249 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`HttpBody`].
+
+use crate::SseEvent;
+use std::time::Duration;
+
+/// Data of the response body that arrived at once, i.e., with a single read
+/// from the socket.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyChunk {
+    /// Duration between sending the request and the arrival of the chunk.
+    time: Duration,
+    /// Size of the payload in bytes, i.e., without the framing of the chunked
+    /// transfer encoding.
+    size: usize,
+}
+
+impl BodyChunk {
+    pub(crate) const fn new(time: Duration, size: usize) -> Self {
+        Self { time, size }
+    }
+
+    /// Returns the duration between sending the request and the arrival of
+    /// the chunk. This has the same base as the relative duration of
+    /// [`TtfbOutcome::ttfb_duration`](crate::TtfbOutcome::ttfb_duration).
+    #[must_use]
+    pub const fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Returns the size of the payload in bytes, i.e., without the framing of
+    /// the chunked transfer encoding.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Reason why reading the response body stopped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BodyEnd {
+    /// The body was received completely.
+    Complete,
+    /// The server closed the connection before the body was complete.
+    ConnectionClosed,
+    /// No data arrived within
+    /// [`StreamOptions::idle_timeout`](crate::StreamOptions::idle_timeout).
+    IdleTimeout,
+    /// The observation window
+    /// [`StreamOptions::window`](crate::StreamOptions::window) elapsed.
+    WindowElapsed,
+}
+
+/// Timeline of the response body, if it was read via
+/// [`HttpOptions::stream`](crate::HttpOptions::stream).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpBody {
+    /// The chunks in the order of their arrival.
+    chunks: Vec<BodyChunk>,
+    /// Why reading stopped.
+    end: BodyEnd,
+    /// Duration between the first byte of the response and the end of
+    /// reading.
+    read_duration: Duration,
+    /// The parsed events, if the body was parsed as event stream.
+    events: Option<Vec<SseEvent>>,
+}
+
+impl HttpBody {
+    pub(crate) const fn new(
+        chunks: Vec<BodyChunk>,
+        end: BodyEnd,
+        read_duration: Duration,
+        events: Option<Vec<SseEvent>>,
+    ) -> Self {
+        Self {
+            chunks,
+            end,
+            read_duration,
+            events,
+        }
+    }
+
+    /// Returns the chunks in the order of their arrival.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn chunks(&self) -> &[BodyChunk] {
+        &self.chunks
+    }
+
+    /// Returns why reading the body stopped.
+    #[must_use]
+    pub const fn end(&self) -> BodyEnd {
+        self.end
+    }
+
+    /// Returns the duration between the first byte of the response and the
+    /// end of reading, i.e., the observation window.
+    #[must_use]
+    pub const fn read_duration(&self) -> Duration {
+        self.read_duration
+    }
+
+    /// Returns the events of the `text/event-stream`, if the body was parsed
+    /// via
+    /// [`StreamOptions::event_stream`](crate::StreamOptions::event_stream).
+    #[must_use]
+    pub fn events(&self) -> Option<&[SseEvent]> {
+        self.events.as_deref()
+    }
+
+    /// Returns the number of events per second during the observation window
+    /// ([`Self::read_duration`]), if the body was parsed as event stream.
+    /// `None`, if the observation window is zero.
+    #[must_use]
+    pub fn event_rate(&self) -> Option<f64> {
+        self.events
+            .as_ref()
+            .filter(|_| !self.read_duration.is_zero())
+            .map(|events| events.len() as f64 / self.read_duration.as_secs_f64())
+    }
+
+    /// Returns the total size of the payload in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.chunks.iter().map(BodyChunk::size).sum()
+    }
+
+    /// Returns the duration between sending the request and the first byte of
+    /// the body. `None`, if the body is empty.
+    #[must_use]
+    pub fn first_byte_duration(&self) -> Option<Duration> {
+        self.chunks.first().map(BodyChunk::time)
+    }
+
+    /// Returns the longest gap between two consecutive chunks. `None`, if
+    /// there are less than two chunks.
+    #[must_use]
+    pub fn max_gap(&self) -> Option<Duration> {
+        self.gaps().max()
+    }
+
+    /// Returns the mean gap between two consecutive chunks. `None`, if there
+    /// are less than two chunks.
+    #[must_use]
+    pub fn mean_gap(&self) -> Option<Duration> {
+        let gaps = u32::try_from(self.chunks.len().checked_sub(1)?).ok()?;
+        self.gaps().sum::<Duration>().checked_div(gaps)
+    }
+
+    /// Returns the gaps between consecutive chunks.
+    fn gaps(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.chunks
+            .windows(2)
+            .map(|pair| pair[1].time.saturating_sub(pair[0].time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::SseParser;
+
+    #[test]
+    fn gaps() {
+        let chunk = |millis, size| BodyChunk::new(Duration::from_millis(millis), size);
+        let body = HttpBody::new(
+            vec![chunk(10, 5), chunk(20, 5), chunk(50, 10), chunk(60, 1)],
+            BodyEnd::Complete,
+            Duration::from_millis(70),
+            None,
+        );
+        assert_eq!(body.size(), 21);
+        assert_eq!(body.first_byte_duration(), Some(Duration::from_millis(10)));
+        assert_eq!(body.max_gap(), Some(Duration::from_millis(30)));
+        assert_eq!(body.mean_gap(), Some(Duration::from_millis(50) / 3));
+
+        let body = HttpBody::new(
+            vec![chunk(10, 5)],
+            BodyEnd::IdleTimeout,
+            Duration::from_millis(20),
+            None,
+        );
+        assert_eq!(body.max_gap(), None);
+        assert_eq!(body.mean_gap(), None);
+        let body = HttpBody::new(Vec::new(), BodyEnd::Complete, Duration::ZERO, None);
+        assert_eq!(body.first_byte_duration(), None);
+        assert_eq!(body.mean_gap(), None);
+    }
+
+    #[test]
+    fn gaps_of_simultaneous_chunks() {
+        // Chunks that are read at once, e.g., on platforms with a coarse
+        // clock, have the same arrival time.
+        let chunk = BodyChunk::new(Duration::from_millis(10), 5);
+        let body = HttpBody::new(
+            vec![chunk, chunk, chunk],
+            BodyEnd::Complete,
+            Duration::ZERO,
+            None,
+        );
+        assert_eq!(body.size(), 15);
+        assert_eq!(body.max_gap(), Some(Duration::ZERO));
+        assert_eq!(body.mean_gap(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn event_rate() {
+        let mut events = Vec::new();
+        SseParser::default().feed(b"data: a\n\ndata: b\n\n", Duration::ZERO, &mut events);
+        let body = |read_duration| {
+            HttpBody::new(
+                Vec::new(),
+                BodyEnd::WindowElapsed,
+                read_duration,
+                Some(events.clone()),
+            )
+        };
+        assert_eq!(body(Duration::from_millis(500)).event_rate(), Some(4.0));
+        assert_eq!(body(Duration::ZERO).event_rate(), None);
+        let body = HttpBody::new(Vec::new(), BodyEnd::Complete, Duration::from_secs(1), None);
+        assert_eq!(body.event_rate(), None);
+    }
+}
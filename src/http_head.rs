@@ -0,0 +1,114 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`HttpHead`].
+
+/// Head of an HTTP/1.1 message as it was sent or received, i.e., the start
+/// line and the header fields.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpHead {
+    /// The request line or the status line.
+    start_line: String,
+    /// The header fields in their order, with the (trimmed) name and value.
+    headers: Vec<(String, String)>,
+    /// Size of the head in bytes, including the empty line.
+    size: usize,
+}
+
+impl HttpHead {
+    /// Parses the head of a request or a response. Lines without a colon are
+    /// skipped.
+    pub(crate) fn parse(head: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(head);
+        let mut lines = text.lines();
+        let start_line = lines.next().unwrap_or_default().to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self {
+            start_line,
+            headers,
+            size: head.len(),
+        }
+    }
+
+    /// Returns the request line, such as `GET / HTTP/1.1`, or the status
+    /// line, such as `HTTP/1.1 200 OK`.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn start_line(&self) -> &str {
+        &self.start_line
+    }
+
+    /// Returns the header fields (name and value) in the order in which they
+    /// were sent.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the value of the first header with the given name
+    /// (case-insensitive).
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the size of the head in bytes, including the empty line.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_response_head() {
+        let head = b"HTTP/1.1 200 OK\r\n\
+            Content-Type: text/html\r\n\
+            Set-Cookie: a=b; Path=/\r\n\
+            invalid line\r\n\
+            \r\n";
+        let head = HttpHead::parse(head);
+        assert_eq!(head.start_line(), "HTTP/1.1 200 OK");
+        assert_eq!(
+            head.headers(),
+            [
+                ("Content-Type".to_string(), "text/html".to_string()),
+                ("Set-Cookie".to_string(), "a=b; Path=/".to_string()),
+            ]
+        );
+        assert_eq!(head.header("content-type"), Some("text/html"));
+        assert_eq!(head.header("Location"), None);
+        assert_eq!(head.size(), 83);
+    }
+}
@@ -0,0 +1,101 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`HttpUpload`].
+
+use std::time::Duration;
+
+/// Measurements of the transmission of the request body. Part of
+/// [`TtfbOutcome::http_get_send_duration`](crate::TtfbOutcome::http_get_send_duration).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpUpload {
+    /// Duration between sending the request head and receiving
+    /// `100 Continue`, if it was received.
+    continue_duration: Option<Duration>,
+    /// Duration of writing the body.
+    duration: Duration,
+    /// Size of the body in bytes.
+    size: usize,
+}
+
+impl HttpUpload {
+    pub(crate) const fn new(
+        continue_duration: Option<Duration>,
+        duration: Duration,
+        size: usize,
+    ) -> Self {
+        Self {
+            continue_duration,
+            duration,
+            size,
+        }
+    }
+
+    /// Returns the duration between sending the request head and receiving
+    /// `100 Continue`. `None`, if
+    /// [`HttpOptions::expect_continue`](crate::HttpOptions::expect_continue)
+    /// wasn't set or if the server didn't respond in time.
+    #[must_use]
+    pub const fn continue_duration(&self) -> Option<Duration> {
+        self.continue_duration
+    }
+
+    /// Returns the duration of writing the body into the socket. The request
+    /// head is written before and isn't included.
+    ///
+    /// The duration ends when the last byte was handed to the operating
+    /// system, i.e., it doesn't include the time until the server received
+    /// the data that fits into the send buffer of the socket.
+    #[must_use]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the size of the body in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the upload throughput in bytes per second. `None`, if the
+    /// duration is zero, e.g., for an empty body or on platforms with a
+    /// coarse clock.
+    #[must_use]
+    pub fn throughput(&self) -> Option<f64> {
+        (!self.duration.is_zero()).then(|| self.size as f64 / self.duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throughput() {
+        let upload = HttpUpload::new(None, Duration::from_millis(500), 1000);
+        assert!((upload.throughput().unwrap() - 2000.0).abs() < f64::EPSILON);
+        let upload = HttpUpload::new(None, Duration::ZERO, 1000);
+        assert_eq!(upload.throughput(), None);
+    }
+}
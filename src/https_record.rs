@@ -0,0 +1,176 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`HttpsRecord`].
+
+use hickory_resolver::proto::rr::rdata::svcb::{Alpn, EchConfigList, SVCB, SvcParamValue};
+use std::time::Duration;
+
+/// Result of the DNS lookup of the HTTPS resource record (RFC 9460) of the
+/// host. CDNs publish ALPN hints and Encrypted Client Hello (ECH) configs in
+/// this record.
+///
+/// Only records in service mode are considered. Records in alias mode are
+/// not followed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpsRecord {
+    /// Duration of the DNS query for the HTTPS record.
+    lookup_duration: Duration,
+    /// Whether a record in service mode was found.
+    found: bool,
+    /// ALPN protocols that are supported by the service.
+    alpn_protocols: Vec<String>,
+    /// The `ECHConfigList` of the service, if published.
+    ech_config_list: Option<Vec<u8>>,
+}
+
+impl HttpsRecord {
+    /// Creates the result from the returned records. If there are multiple
+    /// records, the one with the highest priority (lowest value) is used.
+    pub(crate) fn from_records<'a>(
+        lookup_duration: Duration,
+        records: impl IntoIterator<Item = &'a SVCB>,
+    ) -> Self {
+        let records = records.into_iter().collect::<Vec<_>>();
+        // If a record in alias mode is present, all records in service mode
+        // must be ignored.
+        let is_alias_mode = records.iter().any(|record| record.svc_priority() == 0);
+        let record = records
+            .into_iter()
+            .filter(|_| !is_alias_mode)
+            .min_by_key(|record| record.svc_priority());
+
+        let mut alpn_protocols = Vec::new();
+        let mut ech_config_list = None;
+        for (_, value) in record.iter().flat_map(|record| record.svc_params()) {
+            match value {
+                SvcParamValue::Alpn(Alpn(protocols)) => alpn_protocols.clone_from(protocols),
+                SvcParamValue::EchConfigList(EchConfigList(list)) => {
+                    ech_config_list = Some(list.clone());
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            lookup_duration,
+            found: record.is_some(),
+            alpn_protocols,
+            ech_config_list,
+        }
+    }
+
+    /// Returns the duration of the DNS query for the HTTPS record. This is
+    /// not part of [`TtfbOutcome::dns_lookup_duration`], as browsers query
+    /// the HTTPS record in parallel to the IP address.
+    ///
+    /// [`TtfbOutcome::dns_lookup_duration`]: crate::TtfbOutcome::dns_lookup_duration
+    #[must_use]
+    pub const fn lookup_duration(&self) -> Duration {
+        self.lookup_duration
+    }
+
+    /// Whether the host has an HTTPS record in service mode.
+    #[must_use]
+    pub const fn found(&self) -> bool {
+        self.found
+    }
+
+    /// Getter for the ALPN protocols that are advertised in the record, such
+    /// as `h2` or `h3`.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn alpn_protocols(&self) -> &[String] {
+        &self.alpn_protocols
+    }
+
+    /// Getter for the encoded `ECHConfigList` that is advertised in the
+    /// record, if any.
+    #[must_use]
+    pub fn ech_config_list(&self) -> Option<&[u8]> {
+        self.ech_config_list.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::proto::rr::Name;
+    use hickory_resolver::proto::rr::rdata::svcb::SvcParamKey;
+
+    fn svcb(priority: u16, svc_params: Vec<(SvcParamKey, SvcParamValue)>) -> SVCB {
+        SVCB::new(priority, Name::root(), svc_params)
+    }
+
+    #[test]
+    fn from_records_uses_record_with_highest_priority() {
+        let records = [
+            svcb(
+                2,
+                vec![(
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["http/1.1".to_string()])),
+                )],
+            ),
+            svcb(
+                1,
+                vec![
+                    (
+                        SvcParamKey::Alpn,
+                        SvcParamValue::Alpn(Alpn(vec!["h3".to_string(), "h2".to_string()])),
+                    ),
+                    (
+                        SvcParamKey::EchConfigList,
+                        SvcParamValue::EchConfigList(EchConfigList(vec![1, 2, 3])),
+                    ),
+                ],
+            ),
+        ];
+        let record = HttpsRecord::from_records(Duration::from_millis(5), &records);
+        assert!(record.found());
+        assert_eq!(record.alpn_protocols(), ["h3", "h2"]);
+        assert_eq!(record.ech_config_list(), Some([1, 2, 3].as_slice()));
+        assert_eq!(record.lookup_duration(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn from_records_ignores_alias_mode() {
+        let records = [
+            svcb(0, vec![]),
+            svcb(
+                1,
+                vec![(
+                    SvcParamKey::EchConfigList,
+                    SvcParamValue::EchConfigList(EchConfigList(vec![1, 2, 3])),
+                )],
+            ),
+        ];
+        let record = HttpsRecord::from_records(Duration::ZERO, &records);
+        assert!(!record.found());
+        assert_eq!(record.ech_config_list(), None);
+
+        let record = HttpsRecord::from_records(Duration::ZERO, &[]);
+        assert!(!record.found());
+    }
+}
@@ -27,10 +27,28 @@ SOFTWARE.
 //! TLS handshake. This crate currently only supports HTTP/1.1. It can cope with
 //! TLS 1.2 and 1.3.LICENSE.
 //!
-//! See [`ttfb`] which is the main function of the public interface.
+//! See [`ttfb`] which is the main function of the public interface and
+//! [`ttfb_with_options`] for more fine-grained control of the measurement.
 //!
 //! ## Cross Platform
 //! CLI + lib work on Linux, MacOS, and Windows.
+//!
+//! ## TLS Backends
+//! The TLS backend is selected via cargo features:
+//! - `ring` (default): rustls with `ring` as crypto provider
+//! - `aws-lc-rs`: rustls with `aws-lc-rs` as crypto provider
+//! - `fips`: like `aws-lc-rs`, but with the FIPS-validated build of aws-lc
+//! - `native-tls`: the TLS implementation of the platform, such as OpenSSL
+//!
+//! If multiple backends are enabled, [`TlsOptions::backend`] selects one of
+//! them. Without any backend, only `http` URLs are supported.
+//!
+//! ## Serde
+//! With the `serde` feature, [`TtfbOutcome`] and the types of its fields
+//! implement `Serialize` and `Deserialize`, so that outcomes can be persisted
+//! and loaded again. The field names and [`Duration`]s (`secs` and `nanos`)
+//! form the schema. [`TtfbError`] is serialized as [`TtfbErrorInfo`] with
+//! the kind and the message of the error.
 
 #![deny(
     clippy::all,
@@ -44,27 +62,75 @@ SOFTWARE.
 #![deny(missing_debug_implementations)]
 #![deny(rustdoc::all)]
 
-pub use error::{InvalidUrlError, ResolveDnsError, TtfbError};
-pub use outcome::{DurationPair, TtfbOutcome};
+pub use error::{InvalidUrlError, ResolveDnsError, TtfbError, TtfbErrorInfo};
+pub use grpc::{GrpcError, GrpcServingStatus};
+pub use http_body::{BodyChunk, BodyEnd, HttpBody};
+pub use http_head::HttpHead;
+pub use http_upload::HttpUpload;
+pub use https_record::HttpsRecord;
+pub use openmetrics::{
+    render_openmetrics, render_openmetrics_error, render_probe_openmetrics,
+    render_stats_openmetrics,
+};
+pub use options::{
+    HttpOptions, ProbeOptions, Sni, StreamOptions, TlsBackend, TlsOptions, TlsVersion, TtfbOptions,
+    WebSocketOptions,
+};
+pub use outcome::{ConnectionInfo, DurationPair, ProbeOutcome, TtfbOutcome};
+pub use response::InformationalResponse;
+pub use scheme::Scheme;
+pub use sse::SseEvent;
+pub use starttls::StartTlsProtocol;
+pub use stats::{PhaseStats, TtfbStats};
+#[cfg(feature = "rustls")]
+pub use tls::AllowInvalidCertsVerifier;
+pub use tls_info::{CertificateInfo, TlsHandshakeTimings, TlsInfo, TlsResumption};
+pub use websocket::WebSocketInfo;
 
+use grpc::grpc_health_check;
 use hickory_resolver::Resolver as DnsResolver;
-use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
-use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
-use rustls::{ClientConfig, DigitallySignedStruct, Error, SignatureScheme};
-use rustls_connector::RustlsConnector;
-use std::io::{Read as IoRead, Write as IoWrite};
+use hickory_resolver::proto::rr::rdata::HTTPS;
+use hickory_resolver::proto::rr::{RData, RecordType};
+use probe::execute_probe;
+use request::{HttpRequest, build_http11_request};
+use response::{BodyFraming, BufferedStream, is_informational, read_response_head, status_code};
+use sse::SseParser;
+use starttls::negotiate_starttls;
+use std::io::{ErrorKind, Read as IoRead, Write as IoWrite};
 use std::net::{IpAddr, TcpStream};
 use std::str::FromStr;
-use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
+use tls::{TlsConfig, tls_config_if_necessary, tls_handshake_if_necessary};
 use url::Url;
+use websocket::measure_websocket;
 
 mod error;
+mod grpc;
+mod http_body;
+mod http_head;
+mod http_upload;
+mod https_record;
+mod openmetrics;
+mod options;
 mod outcome;
+mod probe;
+mod request;
+mod response;
+mod scheme;
+mod sse;
+mod starttls;
+mod stats;
+mod tls;
+mod tls_info;
+mod websocket;
 
 const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// How long to wait for `100 Continue` before the body is sent anyway. This
+/// is the default of `curl`.
+const EXPECT_CONTINUE_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Trait that combines [`IoWrite`] and [`IoRead`]. This is necessary, as
 /// trait combinations such as `dyn A + B` are not allowed in Rust.
 ///
@@ -88,6 +154,11 @@ impl<T: IoRead + IoWrite> IoReadAndWrite for T {}
 ///   - `https://1.1.1.1`
 ///   - `12.34.56.78/foobar` (defaults to `http://`)
 ///   - `12.34.56.78` (defaults to `http://`)
+///   - `wss://phip1611.de/socket` (WebSocket upgrade)
+///   - `grpcs://phip1611.de/my.Service` (gRPC health check of the service, or
+///     of the whole server without a path)
+///
+///   URLs without HTTP, such as `tcp://`, are measured with [`probe()`].
 /// - `allow_insecure_certificates`: if illegal certificates (untrusted,
 ///   expired) should be accepted when https is used. Similar to
 ///   `-k/--insecure` in `curl`.
@@ -98,36 +169,246 @@ pub fn ttfb(
     input: impl AsRef<str>,
     allow_insecure_certificates: bool,
 ) -> Result<TtfbOutcome, TtfbError> {
-    let input = input.as_ref();
-    if input.is_empty() {
-        return Err(TtfbError::InvalidUrl(InvalidUrlError::MissingInput));
+    let options = TtfbOptions {
+        allow_insecure_certificates,
+        ..Default::default()
+    };
+    ttfb_with_options(input, &options)
+}
+
+/// Like [`ttfb`] but with additional [`TtfbOptions`] to control the
+/// measurement.
+///
+/// ## Parameters
+/// - `input`: URL pointing to a HTTP server. See [`ttfb`].
+/// - `options`: See [`TtfbOptions`].
+///
+/// ## Return value
+/// [`TtfbOutcome`] or [`TtfbError`].
+pub fn ttfb_with_options(
+    input: impl AsRef<str>,
+    options: &TtfbOptions,
+) -> Result<TtfbOutcome, TtfbError> {
+    let (input, url, scheme) = parse_input(input.as_ref())?;
+    if scheme.is_probe() {
+        return Err(TtfbError::InvalidUrl(InvalidUrlError::ProbeScheme(
+            url.scheme().to_string(),
+        )));
     }
-    let input = input.to_string();
-    let input = prepend_default_scheme_if_necessary(input);
-    let url = parse_input_as_url(&input)?;
-    // println!("final url: {}", url);
-    check_scheme_is_allowed(&url)?;
+    // No HTTP/1.1 request for gRPC.
+    let request = (!scheme.is_grpc())
+        .then(|| build_http11_request(&url, &options.http))
+        .transpose()?;
+    let Connection {
+        info: connection,
+        mut stream,
+        socket,
+        tls_config,
+    } = connect(input, &url, scheme, options)?;
+    let Some(request) = request else {
+        let alpn_protocol = connection.tls_info().and_then(TlsInfo::alpn_protocol);
+        let exchange = grpc_health_check(&mut stream, &url, alpn_protocol)?;
+        return Ok(TtfbOutcome {
+            grpc_trailers_duration_rel: Some(exchange.trailers_duration),
+            ..TtfbOutcome::new(
+                connection,
+                exchange.send_duration,
+                exchange.headers_duration,
+                Some(exchange.status),
+            )
+        });
+    };
+    let mut stream = BufferedStream::new(stream);
+    let http_exchange =
+        execute_http_request(&mut stream, &socket, &request, options.http.stream.as_ref())?;
+    let websocket = match &request.websocket_key {
+        // The upgrade needs the status, even though the TTFB doesn't.
+        Some(key) if status_code(&http_exchange.response_head)? == 101 => Some(measure_websocket(
+            &mut stream,
+            &socket,
+            key,
+            &http_exchange.response_head,
+            &options.websocket,
+        )?),
+        _ => None,
+    };
+    drop(stream);
 
-    let (addr, dns_duration) = resolve_dns_if_necessary(&url)?;
-    let port = url.port_or_known_default().unwrap();
-    let (tcp, tcp_connect_duration) = tcp_connect(addr, port)?;
+    let tls_resumption = match tls_config {
+        Some(tls_config) if options.tls.resumption => Some(measure_tls_resumption(
+            connection.ip_addr(),
+            connection.port(),
+            &request,
+            &tls_config,
+        )?),
+        _ => None,
+    };
+
+    Ok(TtfbOutcome {
+        informational_responses: http_exchange.informational_responses,
+        tls_resumption,
+        http_upload: http_exchange.upload,
+        http_body: http_exchange.body,
+        websocket,
+        http_request_head: Some(HttpHead::parse(&request.head)),
+        http_response_head: Some(HttpHead::parse(&http_exchange.response_head)),
+        ..TtfbOutcome::new(
+            connection,
+            http_exchange.send_duration,
+            http_exchange.ttfb_duration,
+            http_exchange.status,
+        )
+    })
+}
+
+/// Takes a URL of a service without HTTP and connects to it.
+///
+/// Measures the time for DNS lookup, TCP connection start, STARTTLS
+/// negotiation, and TLS handshake. Afterwards, sends the payload and waits
+/// for the first byte of the response, as configured by [`ProbeOptions`].
+///
+/// ## Parameters
+/// - `input`: URL pointing to the service. Can be one of
+///   - `tcp://phip1611.de:5432` (the port is required)
+///   - `tls://phip1611.de:465` (the port is required)
+///   - `smtp://phip1611.de:587` (STARTTLS, also `imap`, `pop3`, and
+///     `postgres`)
+/// - `options`: See [`TtfbOptions`]. [`TtfbOptions::probe`] and
+///   [`TtfbOptions::tls`] apply.
+///
+/// ## Return value
+/// [`ProbeOutcome`] or [`TtfbError`].
+pub fn probe(input: impl AsRef<str>, options: &TtfbOptions) -> Result<ProbeOutcome, TtfbError> {
+    let (input, url, scheme) = parse_input(input.as_ref())?;
+    if !scheme.is_probe() {
+        return Err(TtfbError::InvalidUrl(InvalidUrlError::HttpScheme(
+            url.scheme().to_string(),
+        )));
+    }
+    let Connection {
+        info: connection,
+        mut stream,
+        socket,
+        ..
+    } = connect(input, &url, scheme, options)?;
+    let exchange = execute_probe(&mut stream, &socket, &options.probe)?;
+    Ok(ProbeOutcome::new(
+        connection,
+        exchange.send_duration,
+        exchange.ttfb_duration,
+        exchange.response,
+    ))
+}
+
+/// An established connection, i.e., after the TLS handshake, if any.
+struct Connection {
+    /// The measurements of the connection setup.
+    info: ConnectionInfo,
+    /// The plaintext or TLS stream.
+    stream: Box<dyn IoReadAndWrite>,
+    /// Handle to the underlying TCP socket that stays usable after the TLS
+    /// handshake, e.g., for timeouts.
+    socket: TcpStream,
+    /// The TLS config, if TLS is used, which can resume the TLS session.
+    tls_config: Option<TlsConfig>,
+}
+
+/// Resolves the host of the URL, connects to it, and performs the STARTTLS
+/// negotiation and the TLS handshake, if the scheme requires them. Measures
+/// the duration of every step.
+fn connect(
+    input: String,
+    url: &Url,
+    scheme: Scheme,
+    options: &TtfbOptions,
+) -> Result<Connection, TtfbError> {
+    let port = url
+        .port()
+        .or_else(|| scheme.default_port())
+        .ok_or(TtfbError::InvalidUrl(InvalidUrlError::MissingPort))?;
+    let (addr, dns_duration) = resolve_dns_if_necessary(url)?;
+    let https_record = match url.domain() {
+        Some(domain) if options.tls.ech && scheme.is_tls() && domain != "localhost" => {
+            Some(lookup_https_record(domain)?)
+        }
+        _ => None,
+    };
+    let ech_config_list = https_record.as_ref().and_then(HttpsRecord::ech_config_list);
+    let tls_config = tls_config_if_necessary(url, options, ech_config_list)?;
+    let (mut tcp, tcp_connect_duration) = tcp_connect(addr, port)?;
+    let starttls = match scheme {
+        Scheme::StartTls(protocol) => Some(negotiate_starttls(&mut tcp, protocol)?),
+        _ => None,
+    };
+    let socket = tcp.try_clone().map_err(TtfbError::OtherStreamError)?;
     // Does TLS handshake if necessary: returns regular TCP stream if regular HTTP is used.
     // We can write to the "tcp" trait object whatever content we want to. The underlying
     // implementation will either send plain text or encrypt it for TLS.
-    let (mut tcp, tls_handshake_duration) =
-        tls_handshake_if_necessary(tcp, &url, allow_insecure_certificates)?;
-    let (http_get_send_duration, http_ttfb_duration) = execute_http_get(&mut tcp, &url)?;
-
-    Ok(TtfbOutcome::new(
-        input,
-        addr,
-        port,
-        dns_duration,
+    let (stream, tls_handshake) = tls_handshake_if_necessary(tcp, tls_config.as_ref(), &[])?;
+    let (tls_handshake_duration_rel, tls_handshake_timings, tls_info) = match tls_handshake {
+        Some(handshake) => (
+            Some(handshake.duration),
+            handshake.timings,
+            Some(handshake.info),
+        ),
+        None => (None, None, None),
+    };
+
+    Ok(Connection {
+        info: ConnectionInfo {
+            user_input: input,
+            ip_addr: addr,
+            port,
+            dns_duration_rel: dns_duration,
+            tcp_connect_duration_rel: tcp_connect_duration,
+            starttls,
+            tls_handshake_duration_rel,
+            tls_handshake_timings,
+            tls_info,
+            https_record,
+        },
+        stream,
+        socket,
+        tls_config,
+    })
+}
+
+/// Connects a second time to the server, which resumes the TLS session of
+/// the previous connection, as the same TLS config (and therefore the same
+/// session cache) is used. The HTTP request is sent as early data (0-RTT), if
+/// the server allows that.
+fn measure_tls_resumption(
+    addr: IpAddr,
+    port: u16,
+    request: &HttpRequest,
+    tls_config: &TlsConfig,
+) -> Result<TlsResumption, TtfbError> {
+    let (tcp, tcp_connect_duration) = tcp_connect(addr, port)?;
+    let socket = tcp.try_clone().map_err(TtfbError::OtherStreamError)?;
+    // With early data, the request is sent with the first flight of the
+    // handshake.
+    let handshake_start = Instant::now();
+    let (tcp, tls_handshake) =
+        tls_handshake_if_necessary(tcp, Some(tls_config), &request.to_bytes())?;
+    // Always Some, as we passed a TLS config.
+    let tls_handshake = tls_handshake.unwrap();
+    let mut tcp = BufferedStream::new(tcp);
+
+    let (http_get_send_duration, http_ttfb_duration) = if tls_handshake.early_data_accepted {
+        let response = wait_for_final_response(&mut tcp, handshake_start, &mut Vec::new())?;
+        (Duration::default(), response.first_byte - handshake_start)
+    } else {
+        let http_exchange = execute_http_request(&mut tcp, &socket, request, None)?;
+        (http_exchange.send_duration, http_exchange.ttfb_duration)
+    };
+
+    Ok(TlsResumption::new(
         tcp_connect_duration,
-        tls_handshake_duration,
+        tls_handshake.duration,
         http_get_send_duration,
         http_ttfb_duration,
-        // http_content_download_duration,
+        tls_handshake.early_data_accepted,
+        tls_handshake.info,
     ))
 }
 
@@ -141,147 +422,319 @@ fn tcp_connect(addr: IpAddr, port: u16) -> Result<(TcpStream, Duration), TtfbErr
     Ok((tcp, tcp_connect_duration))
 }
 
-/// If the scheme is "https", this replaces the TCP-Stream with a `TLS<TCP>`-stream.
-/// All data will be encrypted using the TLS-functionality of the crate `native-tls`.
-/// If TLS is used, it measures the time of the TLS handshake.
-fn tls_handshake_if_necessary(
-    tcp: TcpStream,
-    url: &Url,
-    allow_insecure_certificates: bool,
-) -> Result<(Box<dyn IoReadAndWrite>, Option<Duration>), TtfbError> {
-    if url.scheme() == "https" {
-        let connector: RustlsConnector = if allow_insecure_certificates {
-            ClientConfig::builder()
-                .dangerous()
-                .with_custom_certificate_verifier(Arc::new(AllowInvalidCertsVerifier))
-                .with_no_client_auth()
-                .into()
-        } else {
-            RustlsConnector::new_with_native_certs()
-                .unwrap_or_else(|_| RustlsConnector::new_with_webpki_roots_certs())
-        };
-        let now = Instant::now();
-
-        // hostname not used for DNS, only for certificate validation
-        // can also be a IP address, because Certificates can have the IP-Address in
-        // the "cert subject alternative name" field.
-        let certificate_host = url.host_str().unwrap_or("");
-        let mut stream = connector
-            .connect(certificate_host, tcp)
-            .map_err(Box::new)
-            .map_err(TtfbError::CantVerifyTls)?;
-        stream.flush().map_err(TtfbError::OtherStreamError)?;
-        let tls_handshake_duration = now.elapsed();
-        Ok((Box::new(stream), Some(tls_handshake_duration)))
-    } else {
-        Ok((Box::new(tcp), None))
-    }
+/// Measurements of [`execute_http_request`].
+struct HttpExchange {
+    /// Duration of sending the request, including the upload of the body.
+    send_duration: Duration,
+    /// Duration between sending the request and the first byte of the final
+    /// response.
+    ttfb_duration: Duration,
+    /// Status code of the final response, if it has a valid status line.
+    status: Option<u16>,
+    /// Informational (1xx) responses before the final response.
+    informational_responses: Vec<InformationalResponse>,
+    /// Measurements of the upload of the body, if a body was sent.
+    upload: Option<HttpUpload>,
+    /// Timeline of the response body, if it was read.
+    body: Option<HttpBody>,
+    /// The status line and the headers of the final response.
+    response_head: Vec<u8>,
 }
 
-/// Custom verifier that allows invalid certificates.
-#[derive(Debug)]
-pub struct AllowInvalidCertsVerifier;
-
-impl ServerCertVerifier for AllowInvalidCertsVerifier {
-    fn verify_server_cert(
-        &self,
-        _end_entity: &CertificateDer<'_>,
-        _intermediates: &[CertificateDer<'_>],
-        _server_name: &ServerName<'_>,
-        _ocsp_response: &[u8],
-        _now: UnixTime,
-    ) -> Result<ServerCertVerified, Error> {
-        Ok(ServerCertVerified::assertion())
-    }
-
-    fn verify_tls12_signature(
-        &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &DigitallySignedStruct,
-    ) -> Result<HandshakeSignatureValid, Error> {
-        Ok(HandshakeSignatureValid::assertion())
-    }
-
-    fn verify_tls13_signature(
-        &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &DigitallySignedStruct,
-    ) -> Result<HandshakeSignatureValid, Error> {
-        Ok(HandshakeSignatureValid::assertion())
-    }
-
-    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
-        // Return a list of all.
-        vec![
-            SignatureScheme::RSA_PKCS1_SHA1,
-            SignatureScheme::ECDSA_SHA1_Legacy,
-            SignatureScheme::RSA_PKCS1_SHA256,
-            SignatureScheme::ECDSA_NISTP256_SHA256,
-            SignatureScheme::RSA_PKCS1_SHA384,
-            SignatureScheme::ECDSA_NISTP384_SHA384,
-            SignatureScheme::RSA_PKCS1_SHA512,
-            SignatureScheme::ECDSA_NISTP521_SHA512,
-            SignatureScheme::RSA_PSS_SHA256,
-            SignatureScheme::RSA_PSS_SHA384,
-            SignatureScheme::RSA_PSS_SHA512,
-            SignatureScheme::ED25519,
-            SignatureScheme::ED448,
-        ]
-    }
+/// The head of the final response.
+struct FinalResponse {
+    /// Point in time of the first byte.
+    first_byte: Instant,
+    /// The status code, if the response has a valid status line.
+    status: Option<u16>,
+    /// The status line and the headers.
+    head: Vec<u8>,
 }
 
-/// Executes the HTTP/1.1 GET-Request on the given socket. This works with TCP or `TLS<TCP>`.
-/// Afterwards, it waits for the first byte and measures all the times.
-fn execute_http_get(
-    tcp: &mut Box<dyn IoReadAndWrite>,
-    url: &Url,
-) -> Result<(Duration, Duration), TtfbError> {
-    let header = build_http11_header(url);
+/// Sends the HTTP/1.1 request on the given socket. This works with TCP or `TLS<TCP>`.
+/// Afterwards, it waits for the final response and measures all the times.
+///
+/// If the request expects `100 Continue`, the body is only sent after it was
+/// received. If `stream` is set, the response body is read as well. The
+/// `socket` is the underlying TCP socket of `tcp`, which is used for
+/// timeouts.
+///
+/// A response without a valid status line still yields the TTFB. Only
+/// reading the body, which depends on the status, fails then.
+fn execute_http_request(
+    tcp: &mut BufferedStream<Box<dyn IoReadAndWrite>>,
+    socket: &TcpStream,
+    request: &HttpRequest,
+    stream: Option<&StreamOptions>,
+) -> Result<HttpExchange, TtfbError> {
+    let mut informational_responses = Vec::new();
+    if request.body.is_some() && !request.expect_continue {
+        // The body directly follows the head. Don't let Nagle's algorithm
+        // hold it back until the head was acknowledged.
+        socket
+            .set_nodelay(true)
+            .map_err(TtfbError::OtherStreamError)?;
+    }
     let now = Instant::now();
-    tcp.write_all(header.as_bytes())
+    tcp.write_all(&request.head)
         .map_err(TtfbError::CantConnectHttp)?;
     tcp.flush().map_err(TtfbError::OtherStreamError)?;
-    let get_request_send_duration = now.elapsed();
-    let mut one_byte_buf = [0_u8];
+    let head_send_duration = now.elapsed();
+    let (send_duration, upload, final_response) = match &request.body {
+        Some(body) => {
+            let continue_response = request
+                .expect_continue
+                .then(|| wait_for_continue(tcp, socket, now, &mut informational_responses))
+                .transpose()?;
+            match continue_response {
+                // The server rejected the request before the body was sent,
+                // e.g., with `417 Expectation Failed`.
+                Some(ContinueResponse::FinalResponse(response)) => {
+                    (head_send_duration, None, Some(response))
+                }
+                continue_response => {
+                    let continue_duration = match continue_response {
+                        Some(ContinueResponse::Continue(first_byte)) => {
+                            Some((first_byte - now).saturating_sub(head_send_duration))
+                        }
+                        _ => None,
+                    };
+                    // Only the body is timed, the head was already sent.
+                    let upload_start = Instant::now();
+                    tcp.write_all(body).map_err(TtfbError::CantConnectHttp)?;
+                    tcp.flush().map_err(TtfbError::OtherStreamError)?;
+                    let upload =
+                        HttpUpload::new(continue_duration, upload_start.elapsed(), body.len());
+                    (now.elapsed(), Some(upload), None)
+                }
+            }
+        }
+        None => (head_send_duration, None, None),
+    };
+    let final_response = match final_response {
+        Some(response) => response,
+        None => wait_for_final_response(tcp, now, &mut informational_responses)?,
+    };
+    // Like the informational responses, measured from the begin of sending.
+    let ttfb_duration = (final_response.first_byte - now).saturating_sub(send_duration);
+    let body = stream
+        .map(|stream| read_body(tcp, socket, request, &final_response, ttfb_duration, stream))
+        .transpose()?;
+
+    Ok(HttpExchange {
+        send_duration,
+        ttfb_duration,
+        status: final_response.status,
+        informational_responses,
+        upload,
+        body,
+        response_head: final_response.head,
+    })
+}
+
+/// Response of the server to a request with `Expect: 100-continue`.
+enum ContinueResponse {
+    /// The first byte of `100 Continue` was received at the given point in
+    /// time.
+    Continue(Instant),
+    /// The server didn't respond within [`EXPECT_CONTINUE_TIMEOUT`].
+    Timeout,
+    /// The server sent the final response right away.
+    FinalResponse(FinalResponse),
+}
+
+/// Waits for `100 Continue` after the request head was sent. Other
+/// informational responses are collected and skipped. Their durations are
+/// measured from `request_start`, the begin of sending the request, which is
+/// the reference point of all measurements of the exchange.
+fn wait_for_continue(
+    tcp: &mut BufferedStream<Box<dyn IoReadAndWrite>>,
+    socket: &TcpStream,
+    request_start: Instant,
+    informational_responses: &mut Vec<InformationalResponse>,
+) -> Result<ContinueResponse, TtfbError> {
     let now = Instant::now();
-    tcp.read_exact(&mut one_byte_buf)
-        .map_err(|_e| TtfbError::NoHttpResponse)?;
-    let http_ttfb_duration = now.elapsed();
-
-    // todo can lead to error, not every server responds with EOF
-    // need to parse the request header and get the length from that
-    /*tcp.read_to_end(&mut content)
-        .map_err(|_| TtfbError::CantConnectHttp)?;
-    let http_content_download_duration = now.elapsed();
-    println!("http content:\n{}", unsafe {
-        String::from_utf8_unchecked(content)
-    });*/
-    Ok((
-        get_request_send_duration,
-        http_ttfb_duration,
-        // http_content_download_duration,
+    loop {
+        let timeout = EXPECT_CONTINUE_TIMEOUT.saturating_sub(now.elapsed());
+        if timeout.is_zero() {
+            return Ok(ContinueResponse::Timeout);
+        }
+        socket
+            .set_read_timeout(Some(timeout))
+            .map_err(TtfbError::OtherStreamError)?;
+        let mut one_byte_buf = [0_u8];
+        let res = tcp.read_exact(&mut one_byte_buf);
+        socket
+            .set_read_timeout(None)
+            .map_err(TtfbError::OtherStreamError)?;
+        match res {
+            Ok(()) => {}
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(ContinueResponse::Timeout);
+            }
+            Err(_) => return Err(TtfbError::NoHttpResponse),
+        }
+        let first_byte = Instant::now();
+        let head = read_response_head(tcp, one_byte_buf[0])?;
+        let Some(status) = status_code(&head)
+            .ok()
+            .filter(|&status| is_informational(status))
+        else {
+            return Ok(ContinueResponse::FinalResponse(FinalResponse {
+                first_byte,
+                status: status_code(&head).ok(),
+                head,
+            }));
+        };
+        informational_responses.push(InformationalResponse::from_head(
+            status,
+            first_byte - request_start,
+            &head,
+        ));
+        if status == 100 {
+            return Ok(ContinueResponse::Continue(first_byte));
+        }
+    }
+}
+
+/// Waits for the final response after the request was sent. Informational
+/// responses are collected and skipped. Their durations are measured from
+/// `request_start`, the begin of sending the request, which is the reference
+/// point of all measurements of the exchange. A response without a valid
+/// status line is the final response.
+fn wait_for_final_response(
+    tcp: &mut BufferedStream<Box<dyn IoReadAndWrite>>,
+    request_start: Instant,
+    informational_responses: &mut Vec<InformationalResponse>,
+) -> Result<FinalResponse, TtfbError> {
+    loop {
+        let mut one_byte_buf = [0_u8];
+        tcp.read_exact(&mut one_byte_buf)
+            .map_err(|_e| TtfbError::NoHttpResponse)?;
+        let first_byte = Instant::now();
+        let head = read_response_head(tcp, one_byte_buf[0])?;
+        let Some(status) = status_code(&head)
+            .ok()
+            .filter(|&status| is_informational(status))
+        else {
+            return Ok(FinalResponse {
+                first_byte,
+                status: status_code(&head).ok(),
+                head,
+            });
+        };
+        informational_responses.push(InformationalResponse::from_head(
+            status,
+            first_byte - request_start,
+            &head,
+        ));
+    }
+}
+
+/// Reads the body of the final response and records the arrival of its
+/// chunks. Stops, if no data arrives within the idle timeout or when the
+/// observation window has elapsed. Parses the body as event stream, if
+/// requested. Fails, if the response has no valid status line, as the
+/// framing of the body depends on the status.
+fn read_body(
+    tcp: &mut BufferedStream<Box<dyn IoReadAndWrite>>,
+    socket: &TcpStream,
+    request: &HttpRequest,
+    response: &FinalResponse,
+    ttfb_duration: Duration,
+    stream: &StreamOptions,
+) -> Result<HttpBody, TtfbError> {
+    let status = status_code(&response.head)?;
+    let mut framing = BodyFraming::from_head(&request.method, status, &response.head)?;
+    let mut sse_parser = stream.event_stream.then(SseParser::default);
+    let mut events = Vec::new();
+    let mut chunks = Vec::new();
+    let mut buf = vec![0_u8; 16 * 1024];
+    let mut payload = Vec::new();
+    let end = loop {
+        if framing.is_done() {
+            break BodyEnd::Complete;
+        }
+        let remaining_window = stream
+            .window
+            .map(|window| window.saturating_sub(response.first_byte.elapsed()));
+        if remaining_window.is_some_and(|remaining| remaining.is_zero()) {
+            break BodyEnd::WindowElapsed;
+        }
+        // Whether the read timeout is given by the window instead of the idle
+        // timeout.
+        let window_is_limit = remaining_window.is_some_and(|remaining| {
+            stream
+                .idle_timeout
+                .is_none_or(|idle_timeout| remaining <= idle_timeout)
+        });
+        let timeout = if window_is_limit {
+            remaining_window
+        } else {
+            stream.idle_timeout
+        };
+        socket
+            .set_read_timeout(timeout)
+            .map_err(TtfbError::OtherStreamError)?;
+        let limit = framing.read_limit(buf.len());
+        let len = match tcp.read(&mut buf[..limit]) {
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                break if window_is_limit {
+                    BodyEnd::WindowElapsed
+                } else {
+                    BodyEnd::IdleTimeout
+                };
+            }
+            // Many servers close TLS connections without a `close_notify`.
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset
+                ) =>
+            {
+                0
+            }
+            Err(err) => return Err(TtfbError::OtherStreamError(err)),
+        };
+        if len == 0 {
+            break match framing {
+                BodyFraming::UntilClose => BodyEnd::Complete,
+                _ => BodyEnd::ConnectionClosed,
+            };
+        }
+        let time = ttfb_duration + response.first_byte.elapsed();
+        payload.clear();
+        framing.decode(&buf[..len], &mut payload)?;
+        if !payload.is_empty() {
+            chunks.push(BodyChunk::new(time, payload.len()));
+        }
+        if let Some(sse_parser) = &mut sse_parser {
+            sse_parser.feed(&payload, time, &mut events);
+        }
+    };
+    socket
+        .set_read_timeout(None)
+        .map_err(TtfbError::OtherStreamError)?;
+    Ok(HttpBody::new(
+        chunks,
+        end,
+        response.first_byte.elapsed(),
+        sse_parser.map(|_| events),
     ))
 }
 
-/// Constructs the header for a HTTP/1.1 GET-Request.
-///
-/// Sets the following default headers:
-/// - `Accept-Encoding: gzip, deflate, br, zstd` (default of Chrome v123)
-/// - `User-Agent: ttfb/<version>`
-fn build_http11_header(url: &Url) -> String {
-    format!(
-        "GET {path} HTTP/1.1\r\n\
-        Host: {host}\r\n\
-        User-Agent: ttfb/{version}\r\n\
-        Accept: */*\r\n\
-        Accept-Encoding: gzip, deflate, br, zstd\r\n\
-        \r\n",
-        path = url.path(),
-        host = url.host_str().unwrap(),
-        version = CRATE_VERSION
-    )
+/// Parses the user input into the URL and its supported [`Scheme`]. The input
+/// is returned with the default scheme, if it had none.
+fn parse_input(input: &str) -> Result<(String, Url, Scheme), TtfbError> {
+    if input.is_empty() {
+        return Err(TtfbError::InvalidUrl(InvalidUrlError::MissingInput));
+    }
+    let input = prepend_default_scheme_if_necessary(input.to_string());
+    let url = parse_input_as_url(&input)?;
+    let scheme = check_scheme_is_allowed(&url)?;
+    Ok((input, url, scheme))
 }
 
 /// Parses the string input into an [`Url`] object.
@@ -302,25 +755,23 @@ fn prepend_default_scheme_if_necessary(url: String) -> String {
     }
 }
 
-/// Checks the scheme is on the allow list. Currently, we only allow "http"
-/// and "https".
-fn check_scheme_is_allowed(url: &Url) -> Result<(), TtfbError> {
-    let actual_scheme = url.scheme();
-    let allowed_scheme = actual_scheme == "http" || actual_scheme == "https";
-    if allowed_scheme {
-        Ok(())
-    } else {
-        Err(TtfbError::InvalidUrl(InvalidUrlError::WrongScheme(
-            actual_scheme.to_string(),
-        )))
-    }
+/// Checks the scheme is on the allow list, see [`Scheme`].
+fn check_scheme_is_allowed(url: &Url) -> Result<Scheme, TtfbError> {
+    Scheme::from_name(url.scheme()).ok_or_else(|| {
+        TtfbError::InvalidUrl(InvalidUrlError::WrongScheme(url.scheme().to_string()))
+    })
 }
 
 /// Checks from the URL if we already have an IP address or not.
 /// If the user gave us a domain name, we resolve it using the
 /// [`hickory_resolver`] crate and measure the time for it.
 fn resolve_dns_if_necessary(url: &Url) -> Result<(IpAddr, Option<Duration>), TtfbError> {
-    match url.domain() {
+    // The hosts of non-special schemes, such as "tcp", are opaque, so that
+    // IPv4 addresses appear as domains.
+    match url
+        .domain()
+        .filter(|domain| IpAddr::from_str(domain).is_err())
+    {
         Some(domain) => {
             // shortcut
             if domain.eq("localhost") {
@@ -359,41 +810,13 @@ fn resolve_dns(url: &Url) -> Result<(IpAddr, Duration), TtfbError> {
         .build();
 
     let begin = Instant::now();
-
-    // We do the DNS resolving in a tokio runtime in a background task. There
-    // are two reasons for that:
-    // - I must use tokio because of `hickory_resolver`; I'd like to get rid of
-    //   it
-    // - This library is designed with a blocking API but should be embeddable
-    //   in a tokio runtime. To prevent the start of a tokio runtime in a thread
-    //   already having a tokio runtime, we spawn a dedicated thread.
-    //
-    // For the performance/measurements, this overhead is negligible.
-    //
-    // More info: https://stackoverflow.com/a/62536772/2891595
-    let response = {
-        thread::scope(|s| {
-            s.spawn(|| {
-                let tokio = tokio::runtime::Builder::new_current_thread()
-                    .enable_time()
-                    .enable_io()
-                    .build()
-                    .unwrap();
-                tokio.block_on(async {
-                    resolver
-                        .lookup_ip(url.host_str().unwrap())
-                        .await
-                        .map(|res| res.iter().collect::<Vec<IpAddr>>())
-                        .map_err(|err| {
-                            TtfbError::CantResolveDns(ResolveDnsError::Other(Box::new(err)))
-                        })
-                })
-            })
-            .join()
-            .unwrap()
-        })
-    }?;
-
+    let response = run_dns_query(async {
+        resolver
+            .lookup_ip(url.host_str().unwrap())
+            .await
+            .map(|res| res.iter().collect::<Vec<IpAddr>>())
+            .map_err(|err| TtfbError::CantResolveDns(ResolveDnsError::Other(Box::new(err))))
+    })?;
     let duration = begin.elapsed();
 
     let ipv4_addrs = response
@@ -414,6 +837,62 @@ fn resolve_dns(url: &Url) -> Result<(IpAddr, Duration), TtfbError> {
     }
 }
 
+/// Looks up the HTTPS resource record of the domain, which provides the
+/// configs for Encrypted Client Hello (ECH). Measures the duration.
+fn lookup_https_record(domain: &str) -> Result<HttpsRecord, TtfbError> {
+    let resolver = DnsResolver::builder_tokio()
+        .map_err(TtfbError::CantConfigureDNSError)?
+        .build();
+
+    let begin = Instant::now();
+    let response = run_dns_query(resolver.lookup(domain, RecordType::HTTPS));
+    let duration = begin.elapsed();
+
+    match response {
+        Ok(lookup) => Ok(HttpsRecord::from_records(
+            duration,
+            lookup.iter().filter_map(|rdata| match rdata {
+                RData::HTTPS(HTTPS(svcb)) => Some(svcb),
+                _ => None,
+            }),
+        )),
+        Err(err) if err.is_no_records_found() || err.is_nx_domain() => {
+            Ok(HttpsRecord::from_records(duration, []))
+        }
+        Err(err) => Err(TtfbError::CantResolveDns(ResolveDnsError::Other(Box::new(
+            err,
+        )))),
+    }
+}
+
+/// Runs a DNS query of [`hickory_resolver`] to completion.
+///
+/// We do the DNS resolving in a tokio runtime in a background task. There
+/// are two reasons for that:
+/// - I must use tokio because of `hickory_resolver`; I'd like to get rid of
+///   it
+/// - This library is designed with a blocking API but should be embeddable
+///   in a tokio runtime. To prevent the start of a tokio runtime in a thread
+///   already having a tokio runtime, we spawn a dedicated thread.
+///
+/// For the performance/measurements, this overhead is negligible.
+///
+/// More info: <https://stackoverflow.com/a/62536772/2891595>
+fn run_dns_query<T: Send>(query: impl Future<Output = T> + Send) -> T {
+    thread::scope(|s| {
+        s.spawn(|| {
+            let tokio = tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .enable_io()
+                .build()
+                .unwrap();
+            tokio.block_on(query)
+        })
+        .join()
+        .unwrap()
+    })
+}
+
 #[cfg(all(test, not(network_tests)))]
 mod tests {
     use super::*;
@@ -472,6 +951,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dns_if_necessary_ip_of_opaque_host() {
+        let url = url::Url::from_str("tcp://127.0.0.1:5432").unwrap();
+        assert_eq!(
+            resolve_dns_if_necessary(&url),
+            Ok((IpAddr::from_str("127.0.0.1").unwrap(), None))
+        );
+    }
+
     #[test]
     fn test_check_scheme() {
         check_scheme_is_allowed(
@@ -488,6 +976,12 @@ mod tests {
             .unwrap(),
         )
         .expect("must accept http");
+        check_scheme_is_allowed(&Url::from_str("wss://phip1611.de").unwrap())
+            .expect("must accept wss");
+        check_scheme_is_allowed(&Url::from_str("tls://phip1611.de:465").unwrap())
+            .expect("must accept tls");
+        check_scheme_is_allowed(&Url::from_str("grpcs://phip1611.de").unwrap())
+            .expect("must accept grpcs");
         check_scheme_is_allowed(
             &Url::from_str(&prepend_default_scheme_if_necessary(
                 "ftp://phip1611.de".to_owned(),
@@ -496,6 +990,102 @@ mod tests {
         )
         .expect_err("must not accept ftp");
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_error_serialization() {
+        let err = TtfbError::InvalidUrl(InvalidUrlError::MissingPort);
+        let json = serde_json::to_string(&err).unwrap();
+        let info: TtfbErrorInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(info.kind(), "InvalidUrl");
+        assert_eq!(info.message(), err.to_string());
+        assert_eq!(info, TtfbErrorInfo::from(&err));
+    }
+
+    /// Serves the given number of HTTPS connections on a local port. The
+    /// server supports session resumption and early data (0-RTT), and
+    /// responds after the given delay.
+    #[cfg(feature = "rustls")]
+    fn local_https_server(connections: usize, delay: Duration) -> u16 {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use std::net::TcpListener;
+        use std::sync::Arc;
+
+        #[cfg(feature = "aws-lc-rs")]
+        let provider = rustls::crypto::aws_lc_rs::default_provider();
+        #[cfg(not(feature = "aws-lc-rs"))]
+        let provider = rustls::crypto::ring::default_provider();
+        let cert =
+            CertificateDer::from_pem_slice(include_bytes!("tls/testdata/localhost.crt")).unwrap();
+        let key =
+            PrivateKeyDer::from_pem_slice(include_bytes!("tls/testdata/localhost.key")).unwrap();
+        let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        config.max_early_data_size = 16 * 1024;
+        let config = Arc::new(config);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for tcp in listener.incoming().take(connections) {
+                let mut tcp = tcp.unwrap();
+                let mut conn = rustls::ServerConnection::new(config.clone()).unwrap();
+                let mut request = Vec::new();
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    conn.complete_io(&mut tcp).unwrap();
+                    if let Some(mut early_data) = conn.early_data() {
+                        early_data.read_to_end(&mut request).unwrap();
+                    }
+                    // Fails with `WouldBlock` once the buffer is empty.
+                    let _ = conn.reader().read_to_end(&mut request);
+                }
+                thread::sleep(delay);
+                conn.writer()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                    .unwrap();
+                conn.send_close_notify();
+                conn.complete_io(&mut tcp).unwrap();
+            }
+        });
+        port
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_tls_resumption_with_early_data() {
+        let delay = Duration::from_millis(50);
+        let port = local_https_server(2, delay);
+        let options = TtfbOptions {
+            allow_insecure_certificates: true,
+            tls: TlsOptions {
+                resumption: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let outcome = ttfb_with_options(format!("https://127.0.0.1:{port}"), &options).unwrap();
+        let resumption = outcome.tls_resumption().unwrap();
+        assert!(resumption.tls_info().resumed());
+        assert!(resumption.early_data_accepted());
+        assert_eq!(
+            resumption.http_get_send_duration().relative(),
+            Duration::ZERO
+        );
+        // The request was sent with the first flight of the handshake, so the
+        // TTFB includes the handshake and the delay of the server.
+        let ttfb = resumption.ttfb_duration();
+        assert!(ttfb.relative() >= delay);
+        assert!(ttfb.relative() >= resumption.tls_handshake_duration().relative());
+        assert_eq!(
+            ttfb.total(),
+            resumption.tcp_connect_duration().total() + ttfb.relative()
+        );
+    }
 }
 
 /// Tests that rely on an external network connection.
@@ -544,6 +1134,42 @@ mod network_tests {
         assert!(r.tls_handshake_duration().is_some());
     }
 
+    #[test]
+    fn test_https_tls_info() {
+        let r = ttfb("https://phip1611.de".to_string(), false).unwrap();
+        let tls_info = r.tls_info().expect("must have TLS info");
+        assert!(!tls_info.peer_certificates().is_empty());
+    }
+
+    #[test]
+    fn test_https_tls_resumption() {
+        let options = TtfbOptions {
+            tls: TlsOptions {
+                resumption: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let r = ttfb_with_options("https://phip1611.de", &options).unwrap();
+        assert!(r.tls_resumption().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "aws-lc-rs")]
+    fn test_https_ech() {
+        let options = TtfbOptions {
+            tls: TlsOptions {
+                ech: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let r = ttfb_with_options("https://crypto.cloudflare.com/cdn-cgi/trace", &options).unwrap();
+        let https_record = r.https_record().unwrap();
+        assert!(https_record.ech_config_list().is_some());
+        assert_eq!(r.tls_info().unwrap().ech_accepted(), Some(true));
+    }
+
     #[test]
     fn test_https_expired_certificate_error() {
         let r = ttfb("https://expired.badssl.com".to_string(), false);
@@ -556,6 +1182,18 @@ mod network_tests {
         assert!(r.dns_lookup_duration().is_some());
     }
 
+    #[test]
+    fn test_https_expired_certificate_ignore_error_reports_reason() {
+        let r = ttfb("https://expired.badssl.com".to_string(), true).unwrap();
+        let tls_info = r.tls_info().expect("must have TLS info");
+        assert!(tls_info.verification_error().is_some());
+        assert!(
+            tls_info.peer_certificates()[0]
+                .remaining_validity()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_https_self_signed_certificate_error() {
         let r = ttfb("https://self-signed.badssl.com".to_string(), false);
@@ -42,16 +42,63 @@ SOFTWARE.
 #![deny(missing_debug_implementations)]
 #![deny(rustdoc::all)]
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
 use crossterm::ExecutableCommand;
 use crossterm::style::{Attribute, SetAttribute};
-use std::io::stdout;
+use serde_json::{Value, json};
+use std::fs::OpenOptions;
+use std::io::{Write, stdout};
+use std::path::PathBuf;
 use std::process::exit;
+use std::time::{Duration, SystemTime};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use ttfb::ConnectionInfo;
+use ttfb::DurationPair;
+use ttfb::GrpcServingStatus;
+use ttfb::HttpHead;
+use ttfb::HttpUpload;
+use ttfb::ProbeOutcome;
+use ttfb::Scheme;
+use ttfb::TlsHandshakeTimings;
+use ttfb::TlsInfo;
+use ttfb::TlsResumption;
 use ttfb::TtfbError;
 use ttfb::TtfbOutcome;
+use ttfb::{BodyEnd, HttpBody, SseEvent, StreamOptions};
+use ttfb::{HttpOptions, Sni, TlsBackend, TlsOptions, TlsVersion, TtfbOptions};
+use ttfb::{ProbeOptions, WebSocketInfo, WebSocketOptions};
+use url::Url;
 
 const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Exit code if the measurement failed.
+const EXIT_CODE_ERROR: i32 = -1;
+/// Exit code if a certificate of the server expires soon. See
+/// `--cert-expiry-warn-days`.
+const EXIT_CODE_CERT_EXPIRY: i32 = 2;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Headers whose values are masked in HAR files, as they carry credentials.
+const HAR_MASKED_HEADERS: [&str; 2] = ["Authorization", "Proxy-Authorization"];
+/// Replacement of the values of [`HAR_MASKED_HEADERS`].
+const HAR_MASK: &str = "********";
+
+/// Observation window of `--sse`, if `--window` isn't given.
+const DEFAULT_SSE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Timeout of `--ws-ping` and `--ws-first-message`, if `--ws-timeout` isn't
+/// given.
+const DEFAULT_WS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout of `--payload` and `--banner`, if `--probe-timeout` isn't given.
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of bytes of the response of a probe that are shown.
+const MAX_SHOWN_PROBE_RESPONSE: usize = 64;
+
 macro_rules! unwrap_or_exit {
     ($ident:ident) => {
         if let Err(err) = $ident {
@@ -70,23 +117,894 @@ macro_rules! unwrap_or_exit {
     requests. This includes data of intermediate steps, such as the relative \
     and absolute timings of DNS lookup, TCP connect, and TLS handshake. \
     \n\n\
-    For issues or merge requests, please visit https://github.com/phip1611/ttfb."
+    For issues or merge requests, please visit https://github.com/phip1611/ttfb.",
+    after_help = "Exit codes: 0 on success, 255 if the measurement failed, and 2 if a \
+    certificate expires within the period given by --cert-expiry-warn-days."
 )]
 struct TtfbArgs {
     /// Name of the host. An IP address or a URL. "https://"-prefix must be provided for HTTPS/TLS.
     host: String,
+    /// HTTP request method, such as `POST`. Defaults to `POST` if a body is
+    /// given, and to `GET` otherwise.
+    #[arg(short = 'X', long = "request", value_name = "METHOD")]
+    method: Option<String>,
+    /// Additional request header, such as "Authorization: Bearer foo". Can be
+    /// given multiple times. Replaces the default header of the same name.
+    /// Like in `curl`, "Name:" removes the default header and "Name;" sends
+    /// the header with an empty value.
+    #[arg(short = 'H', long = "header", value_name = "HEADER", value_parser = parse_header)]
+    headers: Vec<(String, Option<String>)>,
+    /// Request body. Adds a `Content-Length` header.
+    #[arg(long = "data", value_name = "DATA", conflicts_with = "data_file")]
+    data: Option<String>,
+    /// Read the request body from the given file. Adds a `Content-Length`
+    /// header.
+    #[arg(long = "data-file", value_name = "FILE")]
+    data_file: Option<PathBuf>,
+    /// Send the request body only after the server responded with
+    /// `100 Continue` (`Expect: 100-continue`), or after one second without
+    /// a response. Without a body, this has no effect.
+    #[arg(long = "expect-continue")]
+    expect_continue: bool,
+    /// Keep reading the response body after the head and report the time to
+    /// the first body byte and the gaps between the arriving chunks. Useful
+    /// for streaming endpoints. With `-v`, every chunk is shown.
+    #[arg(long = "stream", group = "streaming")]
+    stream: bool,
+    /// Server-Sent Events mode: like `--stream`, but sends
+    /// `Accept: text/event-stream` and reports the time to the first complete
+    /// event and the event rate. With `-v`, every event is shown.
+    #[arg(long = "sse", group = "streaming")]
+    sse: bool,
+    /// With `--stream` or `--sse`, stop reading if no data arrived for the
+    /// given number of seconds.
+    #[arg(long = "idle-timeout", value_name = "SECONDS", requires = "streaming", value_parser = parse_seconds)]
+    idle_timeout: Option<Duration>,
+    /// With `--stream` or `--sse`, stop reading the given number of seconds
+    /// after the response started. Defaults to 10 seconds with `--sse`, as
+    /// event streams are usually endless.
+    #[arg(long = "window", value_name = "SECONDS", requires = "streaming", value_parser = parse_seconds)]
+    window: Option<Duration>,
+    /// For `ws://` and `wss://` URLs, send a ping after the upgrade and
+    /// report the round trip until the pong.
+    #[arg(long = "ws-ping")]
+    ws_ping: bool,
+    /// For `ws://` and `wss://` URLs, wait for the first message of the
+    /// server after the upgrade and report its arrival.
+    #[arg(long = "ws-first-message")]
+    ws_first_message: bool,
+    /// Number of seconds to wait for the pong of `--ws-ping` and for the
+    /// message of `--ws-first-message`. Defaults to 5 seconds.
+    #[arg(long = "ws-timeout", value_name = "SECONDS", value_parser = parse_seconds)]
+    ws_timeout: Option<Duration>,
+    /// For `tcp://`, `tls://`, and the STARTTLS URLs (`smtp://`, `imap://`,
+    /// `pop3://`, `postgres://`), send the given payload after the connect or
+    /// handshake and report the time to the first byte of the response.
+    #[arg(long = "payload", value_name = "DATA", conflicts_with = "payload_file")]
+    payload: Option<String>,
+    /// Like `--payload`, but reads the payload from the given file.
+    #[arg(long = "payload-file", value_name = "FILE")]
+    payload_file: Option<PathBuf>,
+    /// For `tcp://` and `tls://` URLs, wait for the first byte of the server
+    /// without sending a payload, as for protocols in which the server speaks
+    /// first, such as SMTP or SSH.
+    #[arg(long = "banner")]
+    banner: bool,
+    /// Number of seconds to wait for the response of `--payload` or
+    /// `--banner`. Defaults to 5 seconds.
+    #[arg(long = "probe-timeout", value_name = "SECONDS", value_parser = parse_seconds)]
+    probe_timeout: Option<Duration>,
     /// Whether insecure TLS-certificates (e.g., expired, wrong domain name) are allowed.
     /// Similar to `-k` of `curl`.
     #[arg(short = 'k', long = "insecure")]
     allow_insecure_certificates: bool,
+    /// Print additional details, such as the negotiated TLS parameters and
+    /// the certificate chain of the server.
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// Connect a second time to measure the handshake of a resumed TLS
+    /// session, as it is done by returning visitors. For TLS 1.3, the request
+    /// is sent as early data (0-RTT), if the server allows that.
+    #[arg(long = "tls-resumption")]
+    tls_resumption: bool,
+    /// Only offer the given TLS version. By default, TLS 1.2 and TLS 1.3 are
+    /// offered.
+    #[arg(long = "tls-version", value_enum)]
+    tls_version: Option<TlsVersionArg>,
+    /// Comma-separated list of cipher suites to offer, such as
+    /// `TLS13_AES_128_GCM_SHA256,TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`.
+    #[arg(long = "cipher-suites", value_delimiter = ',')]
+    cipher_suites: Vec<String>,
+    /// Comma-separated list of key-exchange groups to offer, such as
+    /// `X25519,secp256r1`.
+    #[arg(long = "kx-groups", value_delimiter = ',')]
+    kx_groups: Vec<String>,
+    /// Prefer the post-quantum hybrid key-exchange group `X25519MLKEM768`, so
+    /// that its key share is sent in the `ClientHello`. Requires a build with
+    /// the `aws-lc-rs` feature.
+    #[arg(long = "pq")]
+    post_quantum: bool,
+    /// Use Encrypted Client Hello (ECH) with the config that is published in
+    /// the HTTPS DNS record of the host. Requires a build with the
+    /// `aws-lc-rs` feature.
+    #[arg(long = "ech")]
+    ech: bool,
+    /// Send the given Server Name Indication (SNI) instead of the host of the
+    /// URL. The certificate is verified against this name.
+    #[arg(long = "sni", conflicts_with = "no_sni")]
+    sni: Option<String>,
+    /// Don't send a Server Name Indication (SNI) at all.
+    #[arg(long = "no-sni")]
+    no_sni: bool,
+    /// Warn and exit with code 2, if the leaf or an intermediate certificate
+    /// presented by the server expires within the given number of days.
+    #[arg(long = "cert-expiry-warn-days", value_name = "DAYS")]
+    cert_expiry_warn_days: Option<u64>,
+    /// Append the TLS session secrets to the given file (NSS key log format),
+    /// e.g., to decrypt a packet capture with Wireshark. Defaults to the
+    /// value of the `SSLKEYLOGFILE` environment variable, if set.
+    #[arg(long = "keylog", value_name = "FILE")]
+    key_log_file: Option<PathBuf>,
+    /// TLS implementation that performs the handshake. Which backends are
+    /// available depends on the cargo features of the build. By default,
+    /// rustls is used, if available.
+    #[arg(long = "tls-backend", value_enum)]
+    tls_backend: Option<TlsBackendArg>,
+    /// Output format. The machine-readable formats contain the relative and
+    /// total duration of every step, and errors as structured fields. The
+    /// durations are in nanoseconds for `json` and `json-lines`, and in
+    /// milliseconds otherwise.
+    #[arg(long = "output", value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// Append the result to the given file instead of printing it. For
+    /// `csv` and `tsv`, the header row is only written to an empty file, so
+    /// that the results of repeated runs form a single table. For `har`, the
+    /// entry is added to the archive in the file. For `openmetrics`, the file
+    /// is atomically replaced, as required by the textfile collector of the
+    /// Prometheus node exporter. Requires a machine-readable `--output`
+    /// format.
+    #[arg(long = "output-file", value_name = "FILE")]
+    output_file: Option<PathBuf>,
+    /// Don't print the header row of `csv` and `tsv`, e.g., to append the
+    /// results of repeated runs to the same table on stdout.
+    #[arg(long = "no-header")]
+    no_header: bool,
+}
+
+/// Output formats of the results.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable tables
+    Table,
+    /// A pretty-printed JSON object
+    Json,
+    /// A JSON object on a single line (JSON Lines), so that the results of
+    /// repeated runs can be appended to a file
+    JsonLines,
+    /// A header row and a row with the result, with the durations in
+    /// milliseconds (comma-separated values)
+    Csv,
+    /// Like `csv`, but tab-separated
+    Tsv,
+    /// An HTTP Archive (HAR 1.2), which can be loaded into the network tab of
+    /// browsers and other HAR viewers. Only for HTTP URLs
+    Har,
+    /// Gauges for every step in the OpenMetrics text format, labelled by
+    /// target, IP, and protocol, e.g., for the textfile collector of the
+    /// Prometheus node exporter
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+}
+
+/// CLI representation of [`TlsBackend`].
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum TlsBackendArg {
+    /// rustls
+    #[cfg(feature = "rustls")]
+    Rustls,
+    /// The TLS implementation of the platform, such as OpenSSL
+    #[cfg(feature = "native-tls")]
+    NativeTls,
+}
+
+impl From<TlsBackendArg> for TlsBackend {
+    fn from(value: TlsBackendArg) -> Self {
+        match value {
+            #[cfg(feature = "rustls")]
+            TlsBackendArg::Rustls => Self::Rustls,
+            #[cfg(feature = "native-tls")]
+            TlsBackendArg::NativeTls => Self::NativeTls,
+        }
+    }
+}
+
+/// CLI representation of [`TlsVersion`].
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum TlsVersionArg {
+    /// TLS 1.2
+    #[value(name = "1.2")]
+    Tls12,
+    /// TLS 1.3
+    #[value(name = "1.3")]
+    Tls13,
+}
+
+impl From<TlsVersionArg> for TlsVersion {
+    fn from(value: TlsVersionArg) -> Self {
+        match value {
+            TlsVersionArg::Tls12 => Self::Tls12,
+            TlsVersionArg::Tls13 => Self::Tls13,
+        }
+    }
+}
+
+/// Parses a header in the format of `curl`: "Name: value", "Name:" (remove
+/// default header), or "Name;" (empty value).
+fn parse_header(header: &str) -> Result<(String, Option<String>), String> {
+    if let Some((name, value)) = header.split_once(':') {
+        let value = value.trim();
+        Ok((
+            name.trim().to_string(),
+            (!value.is_empty()).then(|| value.to_string()),
+        ))
+    } else if let Some(name) = header.strip_suffix(';') {
+        Ok((name.trim().to_string(), Some(String::new())))
+    } else {
+        Err("expected \"Name: value\", \"Name:\", or \"Name;\"".to_string())
+    }
+}
+
+/// Parses a positive number of seconds, such as `1.5`.
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    seconds
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds > 0.0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| "expected a positive number of seconds".to_string())
+}
+
+/// Label of the row of the HTTP request in the tables, such as
+/// `HTTP GET Req`, `WS Upgrade Req` for WebSocket URLs, `gRPC Check Req` for
+/// gRPC URLs, or `Payload Send` for probes.
+fn request_label(scheme: Option<Scheme>, method: &str) -> String {
+    match scheme {
+        Some(scheme) if scheme.is_websocket() => return "WS Upgrade Req".to_string(),
+        Some(scheme) if scheme.is_grpc() => return "gRPC Check Req".to_string(),
+        Some(scheme) if scheme.is_probe() => return "Payload Send".to_string(),
+        _ => {}
+    }
+    let label = format!("HTTP {method} Req");
+    if label.len() <= 14 {
+        label
+    } else {
+        "HTTP Request".to_string()
+    }
+}
+
+/// Outcome of a measurement. URLs with a probe scheme, such as `tcp://`,
+/// are measured without HTTP.
+#[derive(Debug)]
+enum Outcome {
+    Http(Box<TtfbOutcome>),
+    Probe(Box<ProbeOutcome>),
+}
+
+impl Outcome {
+    /// Measures the URL with [`ttfb::probe`] or [`ttfb::ttfb_with_options`],
+    /// depending on its scheme.
+    fn measure(input: &str, options: &TtfbOptions) -> Result<Self, TtfbError> {
+        match Scheme::from_input(input) {
+            Ok(scheme) if scheme.is_probe() => {
+                ttfb::probe(input, options).map(|probe| Self::Probe(Box::new(probe)))
+            }
+            _ => ttfb::ttfb_with_options(input, options).map(|ttfb| Self::Http(Box::new(ttfb))),
+        }
+    }
+
+    const fn connection(&self) -> &ConnectionInfo {
+        match self {
+            Self::Http(ttfb) => ttfb.connection(),
+            Self::Probe(probe) => probe.connection(),
+        }
+    }
+
+    /// Returns the HTTP outcome, or `None` for probes.
+    const fn http(&self) -> Option<&TtfbOutcome> {
+        match self {
+            Self::Http(ttfb) => Some(ttfb),
+            Self::Probe(_) => None,
+        }
+    }
+
+    fn send_duration(&self) -> Option<DurationPair> {
+        match self {
+            Self::Http(ttfb) => Some(ttfb.http_get_send_duration()),
+            Self::Probe(probe) => probe.send_duration(),
+        }
+    }
+
+    fn ttfb_duration(&self) -> Option<DurationPair> {
+        match self {
+            Self::Http(ttfb) => Some(ttfb.ttfb_duration()),
+            Self::Probe(probe) => probe.ttfb_duration(),
+        }
+    }
+}
+
+/// Reads the given file or exits with an error.
+fn read_file_or_exit(path: &PathBuf) -> Vec<u8> {
+    match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) => TtfbArgs::command()
+            .error(
+                ErrorKind::Io,
+                format!("Can't read {}: {err}", path.display()),
+            )
+            .exit(),
+    }
 }
 
 /// Small CLI binary wrapper around the [`ttfb`] lib.
 fn main() {
     let input: TtfbArgs = TtfbArgs::parse();
-    let res = ttfb::ttfb(input.host, input.allow_insecure_certificates);
-    let ttfb = unwrap_or_exit!(res);
-    print_outcome(&ttfb).unwrap();
+    if input.output_file.is_some() && input.output == OutputFormat::Table {
+        TtfbArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--output-file requires a machine-readable --output format",
+            )
+            .exit();
+    }
+    let body = match (input.data, &input.data_file) {
+        (Some(data), _) => Some(data.into_bytes()),
+        (None, Some(path)) => Some(read_file_or_exit(path)),
+        (None, None) => None,
+    };
+    let payload = match (input.payload, &input.payload_file) {
+        (Some(payload), _) => Some(payload.into_bytes()),
+        (None, Some(path)) => Some(read_file_or_exit(path)),
+        (None, None) => None,
+    };
+    let options = TtfbOptions {
+        allow_insecure_certificates: input.allow_insecure_certificates,
+        tls: TlsOptions {
+            backend: input.tls_backend.map(Into::into),
+            resumption: input.tls_resumption,
+            version: input.tls_version.map(Into::into),
+            cipher_suites: input.cipher_suites,
+            kx_groups: input.kx_groups,
+            post_quantum: input.post_quantum,
+            ech: input.ech,
+            sni: match (input.sni, input.no_sni) {
+                (Some(name), _) => Sni::Custom(name),
+                (None, true) => Sni::Disabled,
+                (None, false) => Sni::FromUrl,
+            },
+            key_log_file: input.key_log_file,
+        },
+        http: HttpOptions {
+            method: input.method,
+            headers: input.headers,
+            body,
+            expect_continue: input.expect_continue,
+            stream: (input.stream || input.sse).then_some(StreamOptions {
+                idle_timeout: input.idle_timeout,
+                window: input
+                    .window
+                    .or_else(|| input.sse.then_some(DEFAULT_SSE_WINDOW)),
+                event_stream: input.sse,
+            }),
+        },
+        websocket: WebSocketOptions {
+            ping: input.ws_ping,
+            first_message: input.ws_first_message,
+            timeout: Some(input.ws_timeout.unwrap_or(DEFAULT_WS_TIMEOUT)),
+        },
+        probe: ProbeOptions {
+            payload,
+            banner: input.banner,
+            timeout: Some(input.probe_timeout.unwrap_or(DEFAULT_PROBE_TIMEOUT)),
+        },
+    };
+    let scheme = Scheme::from_input(&input.host).ok();
+    let request_label = request_label(scheme, options.http.effective_method());
+    let is_websocket = scheme.is_some_and(Scheme::is_websocket);
+    #[cfg(feature = "native-tls")]
+    if options.tls.backend.or(TlsBackend::default_backend()) == Some(TlsBackend::NativeTls)
+        && options.tls.key_log_file.is_none()
+        && scheme.is_some_and(Scheme::is_tls)
+        && std::env::var_os("SSLKEYLOGFILE").is_some_and(|file| !file.is_empty())
+    {
+        print_warning("The native-tls backend ignores SSLKEYLOGFILE");
+    }
+    let timestamp = SystemTime::now();
+    let res = Outcome::measure(&input.host, &options);
+    if input.output == OutputFormat::Har {
+        let outcome = unwrap_or_exit!(res);
+        let Some(entry) = outcome.http().and_then(|ttfb| har_entry(ttfb, timestamp)) else {
+            TtfbArgs::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--output har requires an http, https, ws, or wss URL",
+                )
+                .exit();
+        };
+        if let Err(err) = write_har(input.output_file.as_ref(), entry) {
+            TtfbArgs::command().error(ErrorKind::Io, err).exit();
+        }
+        check_certificates(&outcome, input.cert_expiry_warn_days);
+        return;
+    }
+    if input.output == OutputFormat::OpenMetrics {
+        let metrics = match &res {
+            Ok(Outcome::Http(ttfb)) => ttfb::render_openmetrics(ttfb),
+            Ok(Outcome::Probe(probe)) => ttfb::render_probe_openmetrics(probe),
+            Err(err) => ttfb::render_openmetrics_error(&input.host, err),
+        };
+        if let Err(err) = write_metrics(input.output_file.as_ref(), &metrics) {
+            TtfbArgs::command()
+                .error(ErrorKind::Io, format!("Can't write the output: {err}"))
+                .exit();
+        }
+        match res {
+            Ok(outcome) => check_certificates(&outcome, input.cert_expiry_warn_days),
+            Err(_) => exit(EXIT_CODE_ERROR),
+        }
+        return;
+    }
+    if input.output != OutputFormat::Table {
+        let (header, record) = match input.output {
+            OutputFormat::Json => (
+                None,
+                format!("{:#}", outcome_to_json(&input.host, timestamp, &res)),
+            ),
+            OutputFormat::JsonLines => (
+                None,
+                outcome_to_json(&input.host, timestamp, &res).to_string(),
+            ),
+            OutputFormat::Csv => (
+                Some(separated_header(',')),
+                separated_record(',', &input.host, timestamp, &res),
+            ),
+            OutputFormat::Tsv => (
+                Some(separated_header('\t')),
+                separated_record('\t', &input.host, timestamp, &res),
+            ),
+            OutputFormat::Table | OutputFormat::Har | OutputFormat::OpenMetrics => {
+                unreachable!("tables, HAR, and metrics are written separately")
+            }
+        };
+        let header = header.filter(|_| !input.no_header);
+        if let Err(err) = write_output(input.output_file.as_ref(), header.as_deref(), &record) {
+            TtfbArgs::command()
+                .error(ErrorKind::Io, format!("Can't write the output: {err}"))
+                .exit();
+        }
+        match res {
+            Ok(outcome) => check_certificates(&outcome, input.cert_expiry_warn_days),
+            Err(_) => exit(EXIT_CODE_ERROR),
+        }
+        return;
+    }
+    let outcome = unwrap_or_exit!(res);
+    let expect_probe_response = options.probe.payload.is_some() || options.probe.banner;
+    print_outcome(&outcome, &request_label, expect_probe_response).unwrap();
+    if let Some(ttfb) = outcome.http() {
+        print_http_details(ttfb, &options, &request_label, is_websocket, input.verbose);
+    }
+    if input.verbose {
+        if let Some(timings) = outcome.connection().tls_handshake_timings() {
+            print_tls_handshake_timings(timings).unwrap();
+        }
+        if let Some(tls_info) = outcome.connection().tls_info() {
+            print_tls_info(tls_info).unwrap();
+        }
+    }
+
+    check_certificates(&outcome, input.cert_expiry_warn_days);
+}
+
+/// Prints the details of the HTTP exchange, such as the WebSocket, the
+/// upload, the body, and the TLS resumption.
+fn print_http_details(
+    ttfb: &TtfbOutcome,
+    options: &TtfbOptions,
+    request_label: &str,
+    is_websocket: bool,
+    verbose: bool,
+) {
+    if let Some(websocket) = ttfb.websocket() {
+        print_websocket(ttfb, websocket, &options.websocket).unwrap();
+        if !websocket.accept_valid() {
+            print_warning("The Sec-WebSocket-Accept header of the server is invalid");
+        }
+    } else if let Some(status) = ttfb.http_status().filter(|_| is_websocket) {
+        print_warning(&format!(
+            "The WebSocket upgrade failed: the server responded with status {status}"
+        ));
+    }
+    if let Some(http_upload) = ttfb.http_upload() {
+        print_http_upload(http_upload, options.http.expect_continue).unwrap();
+    }
+    if let Some(http_body) = ttfb.http_body() {
+        print_http_body(ttfb, http_body, verbose).unwrap();
+        if let Some(events) = http_body.events() {
+            print_sse_events(ttfb, http_body, events, verbose).unwrap();
+        }
+    }
+    if let Some(tls_resumption) = ttfb.tls_resumption() {
+        print_tls_resumption(ttfb, tls_resumption, request_label).unwrap();
+    }
+}
+
+/// Warns if the certificate verification would have failed, and exits with
+/// [`EXIT_CODE_CERT_EXPIRY`] if a certificate expires within the given
+/// number of days.
+fn check_certificates(outcome: &Outcome, cert_expiry_warn_days: Option<u64>) {
+    if let Some(tls_info) = outcome.connection().tls_info() {
+        if let Some(err) = tls_info.verification_error() {
+            print_warning(&format!(
+                "Certificate verification would have failed because: {err}"
+            ));
+        }
+        if let Some(days) = cert_expiry_warn_days {
+            let certs = tls_info
+                .peer_certificates()
+                .iter()
+                .map(|cert| (cert.subject(), cert.not_after()));
+            if let Some(code) = check_cert_expiry(certs, SystemTime::now(), days) {
+                exit(code);
+            }
+        }
+    }
+}
+
+/// Returns the result of a measurement for `--output json`. All fields are
+/// always present, so that consumers don't have to check for them.
+fn outcome_to_json(host: &str, timestamp: SystemTime, res: &Result<Outcome, TtfbError>) -> Value {
+    let outcome = res.as_ref().ok();
+    let connection = outcome.map(Outcome::connection);
+    let phases = phases(outcome)
+        .into_iter()
+        .map(|(name, duration_pair)| {
+            let phase = duration_pair.map_or(Value::Null, |duration_pair| {
+                json!({
+                    "relative_ns": nanos(duration_pair.relative()),
+                    "total_ns": nanos(duration_pair.total()),
+                })
+            });
+            (name.to_string(), phase)
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let tls = connection
+        .and_then(ConnectionInfo::tls_info)
+        .map(|tls_info| {
+            json!({
+                "backend": tls_info.backend(),
+                "protocol_version": tls_info.protocol_version(),
+                "cipher_suite": tls_info.cipher_suite(),
+                "key_exchange_group": tls_info.key_exchange_group(),
+                "alpn_protocol": tls_info.alpn_protocol(),
+                "resumed": tls_info.resumed(),
+                "verification_error": tls_info.verification_error(),
+            })
+        });
+    json!({
+        "timestamp": format_time(timestamp),
+        "url": connection.map_or(host, ConnectionInfo::user_input),
+        "ip": connection.map(|connection| connection.ip_addr().to_string()),
+        "port": connection.map(ConnectionInfo::port),
+        "http_status": outcome.and_then(Outcome::http).and_then(TtfbOutcome::http_status),
+        "phases": phases,
+        "tls": tls,
+        "error": res.as_ref().err().map(|err| json!({
+            "kind": err.kind(),
+            "message": err.to_string(),
+        })),
+    })
+}
+
+/// Returns the steps of the measurement in their order, with their names in
+/// the machine-readable output formats.
+fn phases(outcome: Option<&Outcome>) -> [(&'static str, Option<DurationPair>); 8] {
+    let connection = outcome.map(Outcome::connection);
+    [
+        (
+            "dns_lookup",
+            connection.and_then(ConnectionInfo::dns_lookup_duration),
+        ),
+        (
+            "tcp_connect",
+            connection.map(ConnectionInfo::tcp_connect_duration),
+        ),
+        (
+            "starttls_banner",
+            connection.and_then(ConnectionInfo::starttls_banner_duration),
+        ),
+        (
+            "starttls_negotiation",
+            connection.and_then(ConnectionInfo::starttls_negotiation_duration),
+        ),
+        (
+            "tls_handshake",
+            connection.and_then(ConnectionInfo::tls_handshake_duration),
+        ),
+        ("request_send", outcome.and_then(Outcome::send_duration)),
+        ("ttfb", outcome.and_then(Outcome::ttfb_duration)),
+        (
+            "grpc_trailers",
+            outcome
+                .and_then(Outcome::http)
+                .and_then(TtfbOutcome::grpc_trailers_duration),
+        ),
+    ]
+}
+
+/// Returns the header row of `--output csv` and `--output tsv`.
+fn separated_header(separator: char) -> String {
+    let mut columns = vec!["timestamp", "url", "ip", "port"]
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for (name, _) in phases(None) {
+        columns.push(format!("{name}_rel_ms"));
+        columns.push(format!("{name}_total_ms"));
+    }
+    columns.extend(["http_status", "error_kind", "error"].map(str::to_string));
+    columns.join(&separator.to_string())
+}
+
+/// Returns the row with the result for `--output csv` and `--output tsv`.
+/// Missing values are empty.
+fn separated_record(
+    separator: char,
+    host: &str,
+    timestamp: SystemTime,
+    res: &Result<Outcome, TtfbError>,
+) -> String {
+    let outcome = res.as_ref().ok();
+    let connection = outcome.map(Outcome::connection);
+    let mut fields = vec![
+        format_time(timestamp),
+        connection
+            .map_or(host, ConnectionInfo::user_input)
+            .to_string(),
+        connection
+            .map(|connection| connection.ip_addr().to_string())
+            .unwrap_or_default(),
+        connection
+            .map(|connection| connection.port().to_string())
+            .unwrap_or_default(),
+    ];
+    let millis = |duration: Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+    for (_, duration_pair) in phases(outcome) {
+        fields.push(
+            duration_pair
+                .map(|pair| millis(pair.relative()))
+                .unwrap_or_default(),
+        );
+        fields.push(
+            duration_pair
+                .map(|pair| millis(pair.total()))
+                .unwrap_or_default(),
+        );
+    }
+    fields.push(
+        outcome
+            .and_then(Outcome::http)
+            .and_then(TtfbOutcome::http_status)
+            .map(|status| status.to_string())
+            .unwrap_or_default(),
+    );
+    let err = res.as_ref().err();
+    fields.push(err.map(|err| err.kind().to_string()).unwrap_or_default());
+    fields.push(err.map(ToString::to_string).unwrap_or_default());
+    fields
+        .iter()
+        .map(|field| escape_field(field, separator))
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+/// Escapes a field of a CSV (RFC 4180) or TSV row. CSV fields with special
+/// characters are quoted. As TSV has no quoting, tabs and line breaks are
+/// replaced by spaces.
+fn escape_field(field: &str, separator: char) -> String {
+    if separator == '\t' {
+        field.replace(['\t', '\r', '\n'], " ")
+    } else if field.contains([separator, '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Prints the header, if any, and the record, or appends them to the file.
+/// The header is only written to an empty file.
+fn write_output(path: Option<&PathBuf>, header: Option<&str>, record: &str) -> std::io::Result<()> {
+    let Some(path) = path else {
+        if let Some(header) = header {
+            println!("{header}");
+        }
+        println!("{record}");
+        return Ok(());
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if let Some(header) = header.filter(|_| file.metadata().is_ok_and(|m| m.len() == 0)) {
+        writeln!(file, "{header}")?;
+    }
+    writeln!(file, "{record}")
+}
+
+/// Prints the metrics or replaces the file with them. The file is replaced
+/// atomically by renaming a temporary file, so that readers, such as the
+/// textfile collector of the node exporter, never see partial metrics.
+fn write_metrics(path: Option<&PathBuf>, metrics: &str) -> std::io::Result<()> {
+    let Some(path) = path else {
+        print!("{metrics}");
+        return Ok(());
+    };
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, metrics)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Returns the entry of an HTTP Archive (HAR 1.2) for the measurement. The
+/// TLS handshake is part of `connect`, as required by HAR. `None` for URLs
+/// without HTTP/1.1.
+fn har_entry(ttfb: &TtfbOutcome, timestamp: SystemTime) -> Option<Value> {
+    let request = ttfb.http_request_head()?;
+    let response = ttfb.http_response_head()?;
+    let ms = |duration_pair: Option<DurationPair>| {
+        duration_pair.map_or(-1.0, |pair| pair.relative().as_secs_f64() * 1000.0)
+    };
+    let dns = ms(ttfb.dns_lookup_duration());
+    let ssl = ms(ttfb.tls_handshake_duration());
+    let connect = ms(Some(ttfb.tcp_connect_duration())) + ssl.max(0.0);
+    let send = ms(Some(ttfb.http_get_send_duration()));
+    let wait = ms(Some(ttfb.ttfb_duration()));
+    let receive = ttfb
+        .http_body()
+        .map_or(0.0, |body| body.read_duration().as_secs_f64() * 1000.0);
+    // Sum of all steps, except unavailable ones (-1).
+    let time = [dns, connect, send, wait, receive]
+        .into_iter()
+        .filter(|ms| *ms >= 0.0)
+        .sum::<f64>();
+    let body_size = ttfb.http_body().map(HttpBody::size).or_else(|| {
+        response
+            .header("Content-Length")
+            .and_then(|length| length.parse().ok())
+    });
+
+    // The input may lack the scheme, which defaults to http.
+    let input = if ttfb.user_input().contains("://") {
+        ttfb.user_input().to_string()
+    } else {
+        format!("http://{}", ttfb.user_input())
+    };
+    let url = Url::parse(&input).ok();
+    let query_string = url
+        .as_ref()
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({"name": name, "value": value}))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // Like browsers, don't export credentials, as HAR files are often shared.
+    let url = url.map_or(input, |mut url| {
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        url.to_string()
+    });
+    let har_headers = |head: &HttpHead| {
+        head.headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if HAR_MASKED_HEADERS
+                    .iter()
+                    .any(|masked| name.eq_ignore_ascii_case(masked))
+                {
+                    HAR_MASK
+                } else {
+                    value
+                };
+                json!({"name": name, "value": value})
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut status_line = response.start_line().splitn(3, ' ');
+    let http_version = status_line.next().unwrap_or_default();
+    let status_text = status_line.nth(1).unwrap_or_default();
+
+    Some(json!({
+        "startedDateTime": format_time(timestamp),
+        "time": time,
+        "request": {
+            "method": request.start_line().split(' ').next().unwrap_or_default(),
+            "url": url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": har_headers(request),
+            "queryString": query_string,
+            "headersSize": request.size(),
+            "bodySize": ttfb.http_upload().map_or(0, HttpUpload::size),
+        },
+        "response": {
+            // Like browsers, 0 for a response without status.
+            "status": ttfb.http_status().unwrap_or_default(),
+            "statusText": status_text,
+            "httpVersion": http_version,
+            "cookies": [],
+            "headers": har_headers(response),
+            "content": {
+                "size": body_size.unwrap_or_default(),
+                "mimeType": response.header("Content-Type").unwrap_or_default(),
+            },
+            "redirectURL": response.header("Location").unwrap_or_default(),
+            "headersSize": response.size(),
+            "bodySize": body_size.map_or(-1, |size| i64::try_from(size).unwrap_or(i64::MAX)),
+        },
+        "cache": {},
+        "timings": {
+            "blocked": -1,
+            "dns": dns,
+            "connect": connect,
+            "send": send,
+            "wait": wait,
+            "receive": receive,
+            "ssl": ssl,
+        },
+        "serverIPAddress": ttfb.ip_addr().to_string(),
+        "connection": ttfb.port().to_string(),
+    }))
+}
+
+/// Prints an HTTP Archive with the entry, or adds the entry to the archive
+/// in the file. A missing or empty file is created as new archive.
+fn write_har(path: Option<&PathBuf>, entry: Value) -> Result<(), String> {
+    let Some(path) = path else {
+        let mut har = new_har();
+        har["log"]["entries"] = json!([entry]);
+        println!("{har:#}");
+        return Ok(());
+    };
+    let mut har = match std::fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => serde_json::from_str(&content)
+            .map_err(|err| format!("{} isn't a HAR file: {err}", path.display()))?,
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Can't read {}: {err}", path.display()));
+        }
+        _ => new_har(),
+    };
+    har.pointer_mut("/log/entries")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| format!("{} has no log.entries", path.display()))?
+        .push(entry);
+    std::fs::write(path, format!("{har:#}\n"))
+        .map_err(|err| format!("Can't write {}: {err}", path.display()))
+}
+
+/// Returns an empty HTTP Archive (HAR 1.2).
+fn new_har() -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "ttfb",
+                "version": CRATE_VERSION,
+            },
+            "entries": [],
+        }
+    })
+}
+
+/// Returns the duration in nanoseconds.
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
 }
 
 fn exit_error(err: TtfbError) -> ! {
@@ -96,23 +1014,77 @@ fn exit_error(err: TtfbError) -> ! {
     eprint!("\u{1b}[0m");
     eprint!("{err}");
     eprintln!();
-    exit(-1)
+    exit(EXIT_CODE_ERROR)
+}
+
+fn print_warning(msg: &str) {
+    eprint!("\u{1b}[33m");
+    eprint!("\u{1b}[1m");
+    eprint!("WARNING: ",);
+    eprint!("\u{1b}[0m");
+    eprint!("{msg}");
+    eprintln!();
+}
+
+/// Prints a warning for every certificate of the chain, given as subject and
+/// end of validity, that expires within the given number of days. Returns
+/// [`EXIT_CODE_CERT_EXPIRY`] if there is at least one such certificate.
+fn check_cert_expiry<'a>(
+    certs: impl IntoIterator<Item = (&'a str, SystemTime)>,
+    now: SystemTime,
+    days: u64,
+) -> Option<i32> {
+    let mut exit_code = None;
+    for (subject, not_after) in certs {
+        if let Some(warning) = cert_expiry_warning(subject, not_after, now, days) {
+            print_warning(&warning);
+            exit_code = Some(EXIT_CODE_CERT_EXPIRY);
+        }
+    }
+    exit_code
+}
+
+/// Returns the warning for a certificate that has expired at `now` or that
+/// expires within the given number of days.
+fn cert_expiry_warning(
+    subject: &str,
+    not_after: SystemTime,
+    now: SystemTime,
+    days: u64,
+) -> Option<String> {
+    match not_after.duration_since(now) {
+        Err(_) => Some(format!(
+            "Certificate '{subject}' expired at {}",
+            format_time(not_after)
+        )),
+        Ok(remaining) if remaining.as_secs() < days * SECONDS_PER_DAY => Some(format!(
+            "Certificate '{subject}' expires in {} days at {}",
+            remaining.as_secs() / SECONDS_PER_DAY,
+            format_time(not_after)
+        )),
+        Ok(_) => None,
+    }
 }
 
-fn print_outcome(ttfb: &TtfbOutcome) -> Result<(), String> {
+fn print_outcome(
+    outcome: &Outcome,
+    request_label: &str,
+    expect_probe_response: bool,
+) -> Result<(), String> {
     stdout()
         .execute(SetAttribute(Attribute::Bold))
         .map_err(|err| err.to_string())?;
+    let connection = outcome.connection();
     println!(
         "TTFB for {url} (by ttfb@v{crate_version})",
-        url = ttfb.user_input(),
+        url = connection.user_input(),
         crate_version = CRATE_VERSION
     );
     println!("PROPERTY        REL TIME (ms)   ABS TIME (ms)");
     stdout()
         .execute(SetAttribute(Attribute::Reset))
         .map_err(|err| err.to_string())?;
-    if let Some(duration_pair) = ttfb.dns_lookup_duration() {
+    if let Some(duration_pair) = connection.dns_lookup_duration() {
         // For DNS, abs and rel time is the same (because it happens first).
         let duration = duration_pair.relative().as_secs_f64() * 1000.0;
         print!(
@@ -126,40 +1098,793 @@ fn print_outcome(ttfb: &TtfbOutcome) -> Result<(), String> {
         }
         println!();
     }
+    if let Some(https_record) = connection.https_record() {
+        print!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13}  ",
+            property = "DNS HTTPS RR",
+            rel_time = https_record.lookup_duration().as_secs_f64() * 1000.0,
+            // Not part of the timeline, as browsers query it in parallel.
+            abs_time = "-",
+        );
+        if https_record.found() {
+            println!(
+                "(ECH config: {ech}, ALPN: {alpn})",
+                ech = if https_record.ech_config_list().is_some() {
+                    "yes"
+                } else {
+                    "no"
+                },
+                alpn = https_record.alpn_protocols().join(","),
+            );
+        } else {
+            println!("(no record)");
+        }
+    }
     println!(
         "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
         property = "TCP connect",
-        rel_time = ttfb.tcp_connect_duration().relative().as_secs_f64() * 1000.0,
-        abs_time = ttfb.tcp_connect_duration().total().as_secs_f64() * 1000.0,
+        rel_time = connection.tcp_connect_duration().relative().as_secs_f64() * 1000.0,
+        abs_time = connection.tcp_connect_duration().total().as_secs_f64() * 1000.0,
     );
-    if let Some(duration_pair) = ttfb.tls_handshake_duration() {
+    if let Some(duration_pair) = connection.starttls_banner_duration() {
         println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+            property = "Server Banner",
+            rel_time = duration_pair.relative().as_secs_f64() * 1000.0,
+            abs_time = duration_pair.total().as_secs_f64() * 1000.0,
+        );
+    }
+    if let (Some(protocol), Some(duration_pair)) = (
+        connection.starttls_protocol(),
+        connection.starttls_negotiation_duration(),
+    ) {
+        println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}  ({protocol})",
+            property = "STARTTLS",
+            rel_time = duration_pair.relative().as_secs_f64() * 1000.0,
+            abs_time = duration_pair.total().as_secs_f64() * 1000.0,
+        );
+    }
+    if let Some(duration_pair) = connection.tls_handshake_duration() {
+        print!(
             "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
             property = "TLS Handshake",
             rel_time = duration_pair.relative().as_secs_f64() * 1000.0,
             // for DNS abs and rel time is the same (because it happens first)
             abs_time = duration_pair.total().as_secs_f64() * 1000.0,
         );
+        match connection.tls_info().and_then(TlsInfo::ech_accepted) {
+            Some(true) => print!("  (ECH accepted)"),
+            Some(false) => print!("  (ECH rejected)"),
+            None if connection.https_record().is_some() => print!("  (ECH not offered)"),
+            None => {}
+        }
+        println!();
+    }
+    if let Some(send_duration) = outcome.send_duration() {
+        println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+            property = request_label,
+            rel_time = send_duration.relative().as_secs_f64() * 1000.0,
+            abs_time = send_duration.total().as_secs_f64() * 1000.0,
+        );
+        let request_start = send_duration.total() - send_duration.relative();
+        let informational_responses = outcome
+            .http()
+            .map(TtfbOutcome::informational_responses)
+            .unwrap_or_default();
+        for response in informational_responses {
+            println!(
+                "{property:<14}: {rel_time:>13}   {abs_time:>13.3}",
+                property = format!("HTTP {}", response.status()),
+                // Not part of the timeline, as it overlaps with the next steps.
+                rel_time = "-",
+                abs_time = (request_start + response.duration()).as_secs_f64() * 1000.0,
+            );
+            for link in response.links() {
+                println!("                Link: {link}");
+            }
+        }
+    }
+
+    let ttfb_label = match outcome {
+        Outcome::Http(ttfb) if ttfb.grpc_trailers_duration().is_some() => "gRPC Resp TTFB",
+        Outcome::Http(_) => "HTTP Resp TTFB",
+        Outcome::Probe(probe) if probe.send_duration().is_some() => "Response TTFB",
+        Outcome::Probe(_) => "Banner TTFB",
+    };
+    let ttfb_row = match outcome.ttfb_duration() {
+        Some(duration_pair) => Some(format!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+            property = ttfb_label,
+            rel_time = duration_pair.relative().as_secs_f64() * 1000.0,
+            abs_time = duration_pair.total().as_secs_f64() * 1000.0,
+        )),
+        None if expect_probe_response => Some(format!(
+            "{property:<14}: {rel_time:>13}   {abs_time:>13}  (nothing received in time)",
+            property = ttfb_label,
+            rel_time = "-",
+            abs_time = "-",
+        )),
+        None => None,
+    };
+    if let Some(ttfb_row) = ttfb_row {
+        stdout()
+            .execute(SetAttribute(Attribute::Bold))
+            .map_err(|err| err.to_string())?;
+        println!("{ttfb_row}");
+        stdout()
+            .execute(SetAttribute(Attribute::Reset))
+            .map_err(|err| err.to_string())?;
+    }
+    if let Some(duration_pair) = outcome.http().and_then(TtfbOutcome::grpc_trailers_duration) {
+        println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}  ({status})",
+            property = "gRPC Trailers",
+            rel_time = duration_pair.relative().as_secs_f64() * 1000.0,
+            abs_time = duration_pair.total().as_secs_f64() * 1000.0,
+            status = GrpcServingStatus::Serving,
+        );
+    }
+    let probe_response = match outcome {
+        Outcome::Http(_) => None,
+        Outcome::Probe(probe) => probe.response(),
+    };
+    if let Some(response) = probe_response {
+        let shown = &response[..response.len().min(MAX_SHOWN_PROBE_RESPONSE)];
+        print!("                Response: {}", shown.escape_ascii());
+        if shown.len() < response.len() {
+            print!("...");
+        }
+        println!();
     }
+
+    Ok(())
+}
+
+fn print_tls_resumption(
+    ttfb: &TtfbOutcome,
+    tls_resumption: &TlsResumption,
+    request_label: &str,
+) -> Result<(), String> {
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("TLS RESUMPTION  REL TIME (ms)   ABS TIME (ms)");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
+    println!(
+        "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+        property = "TCP connect",
+        rel_time = tls_resumption
+            .tcp_connect_duration()
+            .relative()
+            .as_secs_f64()
+            * 1000.0,
+        abs_time = tls_resumption.tcp_connect_duration().total().as_secs_f64() * 1000.0,
+    );
+    println!(
+        "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}  ({kind})",
+        property = "TLS Handshake",
+        rel_time = tls_resumption
+            .tls_handshake_duration()
+            .relative()
+            .as_secs_f64()
+            * 1000.0,
+        abs_time = tls_resumption
+            .tls_handshake_duration()
+            .total()
+            .as_secs_f64()
+            * 1000.0,
+        kind = match (
+            tls_resumption.tls_info().resumed(),
+            tls_resumption.early_data_accepted()
+        ) {
+            (true, true) => "resumed, 0-RTT",
+            (true, false) => "resumed",
+            (false, _) => "not resumed",
+        }
+    );
+    println!(
+        "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+        property = request_label,
+        rel_time = tls_resumption
+            .http_get_send_duration()
+            .relative()
+            .as_secs_f64()
+            * 1000.0,
+        abs_time = tls_resumption
+            .http_get_send_duration()
+            .total()
+            .as_secs_f64()
+            * 1000.0,
+    );
     println!(
         "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
-        property = "HTTP GET Req",
-        rel_time = ttfb.http_get_send_duration().relative().as_secs_f64() * 1000.0,
-        abs_time = ttfb.http_get_send_duration().total().as_secs_f64() * 1000.0,
+        property = "HTTP Resp TTFB",
+        rel_time = tls_resumption.ttfb_duration().relative().as_secs_f64() * 1000.0,
+        abs_time = tls_resumption.ttfb_duration().total().as_secs_f64() * 1000.0,
+    );
+    if let Some(full_handshake) = ttfb.tls_handshake_duration() {
+        println!(
+            "TLS Handshake (full vs. resumed): {full:.3} ms vs. {resumed:.3} ms",
+            full = full_handshake.relative().as_secs_f64() * 1000.0,
+            resumed = tls_resumption
+                .tls_handshake_duration()
+                .relative()
+                .as_secs_f64()
+                * 1000.0,
+        );
+    }
+    Ok(())
+}
+
+fn print_websocket(
+    ttfb: &TtfbOutcome,
+    websocket: &WebSocketInfo,
+    options: &WebSocketOptions,
+) -> Result<(), String> {
+    if !options.ping && !options.first_message {
+        return Ok(());
+    }
+    // Both measurements start at the upgrade.
+    let upgrade = ttfb.ttfb_duration().total();
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("WEBSOCKET       REL TIME (ms)   ABS TIME (ms)");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
+    let rows = [
+        ("Ping RTT", options.ping, websocket.ping_duration()),
+        (
+            "First Message",
+            options.first_message,
+            websocket.first_message_duration(),
+        ),
+    ];
+    for (property, requested, duration) in rows {
+        match duration {
+            Some(duration) => println!(
+                "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+                rel_time = duration.as_secs_f64() * 1000.0,
+                abs_time = (upgrade + duration).as_secs_f64() * 1000.0,
+            ),
+            None if requested => println!(
+                "{property:<14}: {rel_time:>13}   {abs_time:>13}  (not received in time)",
+                rel_time = "-",
+                abs_time = "-",
+            ),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn print_http_upload(http_upload: &HttpUpload, expect_continue: bool) -> Result<(), String> {
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("UPLOAD              TIME (ms)");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
+    match http_upload.continue_duration() {
+        Some(duration) => println!(
+            "{property:<14}: {time:>13.3}",
+            property = "100 Continue",
+            time = duration.as_secs_f64() * 1000.0,
+        ),
+        None if expect_continue => println!(
+            "{property:<14}: {time:>13}   (not received in time)",
+            property = "100 Continue",
+            time = "-",
+        ),
+        None => {}
+    }
+    println!(
+        "{property:<14}: {time:>13.3}   ({size} bytes{throughput})",
+        property = "Body",
+        time = http_upload.duration().as_secs_f64() * 1000.0,
+        size = http_upload.size(),
+        throughput = http_upload
+            .throughput()
+            .map(|throughput| format!(", {:.3} MiB/s", throughput / (1024.0 * 1024.0)))
+            .unwrap_or_default(),
     );
+    Ok(())
+}
 
+fn print_http_body(ttfb: &TtfbOutcome, http_body: &HttpBody, verbose: bool) -> Result<(), String> {
+    // Chunk times are relative to the end of sending the request.
+    let request_sent = ttfb.http_get_send_duration().total();
+    println!();
     stdout()
         .execute(SetAttribute(Attribute::Bold))
         .map_err(|err| err.to_string())?;
+    println!("BODY            REL TIME (ms)   ABS TIME (ms)");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
+    if let Some(duration) = http_body.first_byte_duration() {
+        println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
+            property = "First Byte",
+            rel_time = duration.as_secs_f64() * 1000.0,
+            abs_time = (request_sent + duration).as_secs_f64() * 1000.0,
+        );
+    }
+    if verbose {
+        for (i, chunk) in http_body.chunks().iter().enumerate() {
+            println!(
+                "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}  ({size} bytes)",
+                property = format!("Chunk {}", i + 1),
+                rel_time = chunk.time().as_secs_f64() * 1000.0,
+                abs_time = (request_sent + chunk.time()).as_secs_f64() * 1000.0,
+                size = chunk.size(),
+            );
+        }
+    }
+    if let (Some(max_gap), Some(mean_gap)) = (http_body.max_gap(), http_body.mean_gap()) {
+        println!(
+            "{property:<14}: {time:>13.3}",
+            property = "Max Gap",
+            time = max_gap.as_secs_f64() * 1000.0,
+        );
+        println!(
+            "{property:<14}: {time:>13.3}",
+            property = "Mean Gap",
+            time = mean_gap.as_secs_f64() * 1000.0,
+        );
+    }
+    println!(
+        "{property:<14}: {chunks} chunk(s), {size} bytes, {end}",
+        property = "Summary",
+        chunks = http_body.chunks().len(),
+        size = http_body.size(),
+        end = match http_body.end() {
+            BodyEnd::Complete => "complete",
+            BodyEnd::ConnectionClosed => "connection closed early",
+            BodyEnd::IdleTimeout => "idle timeout",
+            BodyEnd::WindowElapsed => "observation window elapsed",
+        },
+    );
+    Ok(())
+}
+
+fn print_sse_events(
+    ttfb: &TtfbOutcome,
+    http_body: &HttpBody,
+    events: &[SseEvent],
+    verbose: bool,
+) -> Result<(), String> {
+    // Event times are relative to the end of sending the request.
+    let request_sent = ttfb.http_get_send_duration().total();
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("SSE             REL TIME (ms)   ABS TIME (ms)");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
     println!(
         "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}",
-        property = "HTTP Resp TTFB",
+        property = "Open",
         rel_time = ttfb.ttfb_duration().relative().as_secs_f64() * 1000.0,
         abs_time = ttfb.ttfb_duration().total().as_secs_f64() * 1000.0,
     );
+    let shown_events = if verbose {
+        events
+    } else {
+        &events[..events.len().min(1)]
+    };
+    for (i, event) in shown_events.iter().enumerate() {
+        println!(
+            "{property:<14}: {rel_time:>13.3}   {abs_time:>13.3}  ({event_type}, {size} bytes)",
+            property = if i == 0 {
+                "First Event".to_string()
+            } else {
+                format!("Event {}", i + 1)
+            },
+            rel_time = event.time().as_secs_f64() * 1000.0,
+            abs_time = (request_sent + event.time()).as_secs_f64() * 1000.0,
+            event_type = event.event_type(),
+            size = event.data().len(),
+        );
+    }
+    println!(
+        "{property:<14}: {events} event(s) in {window:.3} s{rate}",
+        property = "Summary",
+        events = events.len(),
+        window = http_body.read_duration().as_secs_f64(),
+        rate = http_body
+            .event_rate()
+            .map(|rate| format!(" ({rate:.3} events/s)"))
+            .unwrap_or_default(),
+    );
+    Ok(())
+}
+
+fn print_tls_handshake_timings(timings: &TlsHandshakeTimings) -> Result<(), String> {
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("TLS HANDSHAKE       TIME (ms)");
     stdout()
         .execute(SetAttribute(Attribute::Reset))
         .map_err(|err| err.to_string())?;
+    println!(
+        "{property:<14}: {time:>13.3}   (ClientHello: {size} bytes, {round_trips} round trip(s) in total)",
+        property = "ServerHello",
+        time = timings.server_hello_duration().as_secs_f64() * 1000.0,
+        size = timings.client_hello_size(),
+        round_trips = timings.round_trips(),
+    );
+    println!(
+        "{property:<14}: {time:>13.3}",
+        property = "Cert Verify",
+        time = timings.certificate_verification_duration().as_secs_f64() * 1000.0,
+    );
+    println!(
+        "{property:<14}: {time:>13.3}",
+        property = "Remaining",
+        time = timings.remaining_duration().as_secs_f64() * 1000.0,
+    );
+    println!(
+        "{property:<14}: {time:>13.3}   (before the measurement)",
+        property = "Load Root CAs",
+        time = timings.root_certificates_load_duration().as_secs_f64() * 1000.0,
+    );
+    Ok(())
+}
 
+fn print_tls_info(tls_info: &TlsInfo) -> Result<(), String> {
+    println!();
+    stdout()
+        .execute(SetAttribute(Attribute::Bold))
+        .map_err(|err| err.to_string())?;
+    println!("TLS");
+    stdout()
+        .execute(SetAttribute(Attribute::Reset))
+        .map_err(|err| err.to_string())?;
+    println!("{:<14}: {}", "Backend", tls_info.backend());
+    println!("{:<14}: {}", "Version", tls_info.protocol_version());
+    println!("{:<14}: {}", "Cipher Suite", tls_info.cipher_suite());
+    println!(
+        "{:<14}: {}",
+        "Key Exchange",
+        tls_info.key_exchange_group().unwrap_or("-")
+    );
+    println!(
+        "{:<14}: {}",
+        "ALPN",
+        tls_info.alpn_protocol().unwrap_or("-")
+    );
+    println!(
+        "{:<14}: {}",
+        "Resumed",
+        if tls_info.resumed() { "yes" } else { "no" }
+    );
+    if let Some(accepted) = tls_info.ech_accepted() {
+        println!(
+            "{:<14}: {}",
+            "ECH",
+            if accepted { "accepted" } else { "rejected" }
+        );
+    }
+    if let Some(err) = tls_info.verification_error() {
+        println!("{:<14}: {}", "Verification", err);
+    }
+    for (i, cert) in tls_info.peer_certificates().iter().enumerate() {
+        println!("{:<14}: #{i}", "Certificate");
+        println!("  {:<12}: {}", "Subject", cert.subject());
+        println!("  {:<12}: {}", "Issuer", cert.issuer());
+        if !cert.subject_alt_names().is_empty() {
+            println!("  {:<12}: {}", "SANs", cert.subject_alt_names().join(", "));
+        }
+        println!("  {:<12}: {}", "Not Before", format_time(cert.not_before()));
+        println!("  {:<12}: {}", "Not After", format_time(cert.not_after()));
+    }
     Ok(())
 }
+
+/// Formats a point in time as RFC 3339 timestamp in UTC.
+fn format_time(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "<invalid>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    // 2030-01-01T00:00:00Z
+    const TIMESTAMP_SECS: u64 = 1_893_456_000;
+
+    /// Measures a local HTTP server that answers with the given response.
+    fn local_http_outcome(response: &'static [u8]) -> Outcome {
+        local_http_outcome_with(response, "", &TtfbOptions::default())
+    }
+
+    /// Like [`local_http_outcome`], but with the userinfo (`user:pass@`) in
+    /// the URL and with the given options.
+    fn local_http_outcome_with(
+        response: &'static [u8],
+        userinfo: &str,
+        options: &TtfbOptions,
+    ) -> Outcome {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut tcp, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let n = tcp.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            tcp.write_all(response).unwrap();
+        });
+        let outcome =
+            Outcome::measure(&format!("http://{userinfo}127.0.0.1:{port}/"), options).unwrap();
+        server.join().unwrap();
+        outcome
+    }
+
+    fn timestamp() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(TIMESTAMP_SECS)
+    }
+
+    #[test]
+    fn outcome_to_json_of_measurement() {
+        let res = Ok(local_http_outcome(
+            b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n",
+        ));
+        let json = outcome_to_json("ignored", timestamp(), &res);
+
+        let mut fields = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(
+            fields,
+            [
+                "error",
+                "http_status",
+                "ip",
+                "phases",
+                "port",
+                "timestamp",
+                "tls",
+                "url"
+            ]
+        );
+        assert_eq!(json["timestamp"], "2030-01-01T00:00:00Z");
+        assert!(
+            json["url"]
+                .as_str()
+                .unwrap()
+                .starts_with("http://127.0.0.1:")
+        );
+        assert_eq!(json["ip"], "127.0.0.1");
+        assert!(json["port"].is_u64());
+        assert_eq!(json["http_status"], 204);
+        assert_eq!(json["tls"], Value::Null);
+        assert_eq!(json["error"], Value::Null);
+
+        let phases = json["phases"].as_object().unwrap();
+        assert_eq!(phases.len(), 8);
+        for name in ["tcp_connect", "request_send", "ttfb"] {
+            assert!(phases[name]["relative_ns"].is_u64(), "{name}");
+            assert!(phases[name]["total_ns"].is_u64(), "{name}");
+        }
+        // Steps that didn't happen are null, not absent.
+        for name in [
+            "dns_lookup",
+            "starttls_banner",
+            "starttls_negotiation",
+            "tls_handshake",
+            "grpc_trailers",
+        ] {
+            assert_eq!(phases[name], Value::Null, "{name}");
+        }
+    }
+
+    #[test]
+    fn separated_record_quotes_fields() {
+        let res = Err(TtfbError::InvalidHttpOptions("invalid header".to_string()));
+        let host = "example.com/?a=1,2&b=\"3\"";
+
+        let csv = separated_record(',', host, timestamp(), &res);
+        assert_eq!(
+            csv,
+            format!(
+                "2030-01-01T00:00:00Z,\"example.com/?a=1,2&b=\"\"3\"\"\",{}InvalidHttpOptions,Invalid HTTP options: invalid header",
+                // Empty IP, port, durations, and status.
+                ",".repeat(2 + 2 * 8 + 1)
+            )
+        );
+        assert_eq!(
+            csv.split(',').count(),
+            separated_header(',').split(',').count() + 1,
+            "the quoted comma is the only additional separator"
+        );
+
+        let tsv = separated_record('\t', host, timestamp(), &res);
+        assert_eq!(tsv.split('\t').nth(1), Some(host));
+        assert_eq!(escape_field("a\tb\nc", '\t'), "a b c");
+        assert_eq!(escape_field("a\nb", ','), "\"a\nb\"");
+        assert_eq!(escape_field("plain", ','), "plain");
+    }
+
+    #[test]
+    fn har_entry_is_valid_har() {
+        let outcome = local_http_outcome(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nok",
+        );
+        let entry = har_entry(outcome.http().unwrap(), timestamp()).unwrap();
+
+        // ISO 8601 with time zone, as required by HAR 1.2.
+        let started = entry["startedDateTime"].as_str().unwrap();
+        assert_eq!(started, "2030-01-01T00:00:00Z");
+
+        let timings = &entry["timings"];
+        // Steps that don't apply are -1. There is no DNS lookup for an IP
+        // and no TLS handshake for http.
+        assert_eq!(timings["blocked"], -1);
+        assert_eq!(timings["dns"], -1.0);
+        assert_eq!(timings["ssl"], -1.0);
+        // The required steps are always present and not negative.
+        for name in ["connect", "send", "wait", "receive"] {
+            assert!(timings[name].as_f64().unwrap() >= 0.0, "{name}");
+        }
+        let sum = ["connect", "send", "wait", "receive"]
+            .into_iter()
+            .map(|name| timings[name].as_f64().unwrap())
+            .sum::<f64>();
+        assert!((entry["time"].as_f64().unwrap() - sum).abs() < 1e-9);
+
+        assert_eq!(entry["request"]["method"], "GET");
+        assert_eq!(entry["request"]["httpVersion"], "HTTP/1.1");
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["statusText"], "OK");
+        assert_eq!(entry["response"]["content"]["size"], 2);
+        assert_eq!(entry["response"]["content"]["mimeType"], "text/plain");
+        assert_eq!(entry["serverIPAddress"], "127.0.0.1");
+    }
+
+    #[test]
+    fn har_entry_hides_credentials() {
+        let options = TtfbOptions {
+            http: HttpOptions {
+                headers: vec![(
+                    "Proxy-Authorization".to_string(),
+                    Some("Basic cHJveHk6c2VjcmV0".to_string()),
+                )],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let outcome =
+            local_http_outcome_with(b"HTTP/1.1 204 No Content\r\n\r\n", "user:secret@", &options);
+        let entry = har_entry(outcome.http().unwrap(), timestamp()).unwrap();
+
+        let url = entry["request"]["url"].as_str().unwrap();
+        assert!(url.starts_with("http://127.0.0.1:"), "{url}");
+        let headers = entry["request"]["headers"].as_array().unwrap();
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|header| header["name"] == name)
+                .map(|header| header["value"].clone())
+        };
+        assert_eq!(header("Authorization"), Some(json!(HAR_MASK)));
+        assert_eq!(header("Proxy-Authorization"), Some(json!(HAR_MASK)));
+        // Neither the password nor the encoded credentials.
+        let entry = entry.to_string();
+        assert!(!entry.contains("secret"));
+        assert!(!entry.contains("dXNlcjpzZWNyZXQ="));
+        assert!(!entry.contains("cHJveHk6c2VjcmV0"));
+    }
+
+    #[test]
+    fn write_har_appends_entries() {
+        let path = std::env::temp_dir().join(format!("ttfb-test-{}.har", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        write_har(Some(&path), json!({"id": 1})).unwrap();
+        write_har(Some(&path), json!({"id": 2})).unwrap();
+        let har = serde_json::from_str::<Value>(&std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let har = har.unwrap();
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har["log"]["creator"]["name"], "ttfb");
+        assert_eq!(har["log"]["entries"], json!([{"id": 1}, {"id": 2}]));
+    }
+
+    #[test]
+    fn outcome_to_json_of_error() {
+        let res = Err(TtfbError::InvalidHttpOptions("invalid header".to_string()));
+        let json = outcome_to_json("example.com", timestamp(), &res);
+
+        assert_eq!(
+            json,
+            json!({
+                "timestamp": "2030-01-01T00:00:00Z",
+                "url": "example.com",
+                "ip": null,
+                "port": null,
+                "http_status": null,
+                "phases": {
+                    "dns_lookup": null,
+                    "tcp_connect": null,
+                    "starttls_banner": null,
+                    "starttls_negotiation": null,
+                    "tls_handshake": null,
+                    "request_send": null,
+                    "ttfb": null,
+                    "grpc_trailers": null,
+                },
+                "tls": null,
+                "error": {
+                    "kind": "InvalidHttpOptions",
+                    "message": "Invalid HTTP options: invalid header",
+                },
+            })
+        );
+        // `--output json-lines` prints the same object on a single line.
+        let line = json.to_string();
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<Value>(&line).unwrap(), json);
+    }
+
+    #[test]
+    fn cert_expiry_warning_for_fixed_not_after() {
+        let not_after = timestamp();
+        let days_before = |days| not_after - Duration::from_secs(days * SECONDS_PER_DAY);
+
+        assert_eq!(
+            cert_expiry_warning("CN=a", not_after, days_before(31), 30),
+            None
+        );
+        assert_eq!(
+            cert_expiry_warning("CN=a", not_after, days_before(10), 30).as_deref(),
+            Some("Certificate 'CN=a' expires in 10 days at 2030-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            cert_expiry_warning("CN=a", not_after, not_after + Duration::from_secs(1), 30)
+                .as_deref(),
+            Some("Certificate 'CN=a' expired at 2030-01-01T00:00:00Z")
+        );
+        // Without a period, only expired certificates are reported.
+        assert_eq!(
+            cert_expiry_warning("CN=a", not_after, days_before(1), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn check_cert_expiry_exit_code() {
+        let not_after = timestamp();
+        let now = not_after - Duration::from_secs(10 * SECONDS_PER_DAY);
+        let far_future = not_after + Duration::from_secs(365 * SECONDS_PER_DAY);
+
+        assert_eq!(check_cert_expiry([], now, 30), None);
+        assert_eq!(check_cert_expiry([("CN=leaf", far_future)], now, 30), None);
+        assert_eq!(check_cert_expiry([("CN=leaf", not_after)], now, 5), None);
+        assert_eq!(
+            check_cert_expiry([("CN=leaf", far_future), ("CN=ca", not_after)], now, 30),
+            Some(EXIT_CODE_CERT_EXPIRY)
+        );
+    }
+}
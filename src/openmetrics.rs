@@ -0,0 +1,410 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for rendering the result of a measurement, or the aggregate of
+//! repeated measurements, in the OpenMetrics text exposition format, which is
+//! also understood by Prometheus.
+
+use crate::{
+    ConnectionInfo, DurationPair, PhaseStats, ProbeOutcome, TtfbError, TtfbOutcome, TtfbStats,
+};
+use std::fmt::Write;
+
+/// Renders the outcome in the OpenMetrics text exposition format, e.g., for
+/// the textfile collector of the Prometheus node exporter.
+///
+/// All metrics are gauges and carry the labels `target` (the user input),
+/// `ip`, and `protocol` (the scheme of the URL):
+/// - `ttfb_success`: always `1`, see [`render_openmetrics_error`]
+/// - `ttfb_phase_duration_seconds`: the relative duration of every step,
+///   with the `phase` label, such as `tls_handshake` or `ttfb`
+/// - `ttfb_phase_end_seconds`: the duration from the beginning of the
+///   measurement until the end of every step, with the `phase` label
+/// - `ttfb_http_status`: the status code of the final response, if it has a
+///   valid status line
+#[must_use]
+pub fn render_openmetrics(outcome: &TtfbOutcome) -> String {
+    render(
+        outcome.connection(),
+        [
+            ("request_send", Some(outcome.http_get_send_duration())),
+            ("ttfb", Some(outcome.ttfb_duration())),
+            ("grpc_trailers", outcome.grpc_trailers_duration()),
+        ],
+        outcome.http_status(),
+    )
+}
+
+/// Renders the outcome of a probe in the OpenMetrics text exposition format.
+/// The metrics are the same as of [`render_openmetrics`], but without
+/// `ttfb_http_status`.
+#[must_use]
+pub fn render_probe_openmetrics(outcome: &ProbeOutcome) -> String {
+    render(
+        outcome.connection(),
+        [
+            ("request_send", outcome.send_duration()),
+            ("ttfb", outcome.ttfb_duration()),
+        ],
+        None,
+    )
+}
+
+/// Renders the metrics of the connection setup and of the given steps that
+/// follow it.
+fn render<const N: usize>(
+    connection: &ConnectionInfo,
+    exchange_phases: [(&str, Option<DurationPair>); N],
+    http_status: Option<u16>,
+) -> String {
+    let labels = format!(
+        "target=\"{target}\",ip=\"{ip}\",protocol=\"{protocol}\"",
+        target = escape_label_value(connection.user_input()),
+        ip = connection.ip_addr(),
+        protocol = escape_label_value(protocol(connection.user_input())),
+    );
+    let phases = [
+        ("dns_lookup", connection.dns_lookup_duration()),
+        ("tcp_connect", Some(connection.tcp_connect_duration())),
+        ("starttls_banner", connection.starttls_banner_duration()),
+        (
+            "starttls_negotiation",
+            connection.starttls_negotiation_duration(),
+        ),
+        ("tls_handshake", connection.tls_handshake_duration()),
+    ]
+    .into_iter()
+    .chain(exchange_phases)
+    .filter_map(|(phase, duration_pair)| duration_pair.map(|pair| (phase, pair)))
+    .collect::<Vec<_>>();
+
+    let mut metrics = String::new();
+    write_metric_family(
+        &mut metrics,
+        "ttfb_success",
+        "Whether the measurement succeeded.",
+        [(labels.clone(), 1.0)],
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_duration_seconds",
+        "Duration of the step of the measurement.",
+        phases.iter().map(|(phase, pair)| {
+            (
+                format!("{labels},phase=\"{phase}\""),
+                DurationPair::relative(pair).as_secs_f64(),
+            )
+        }),
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_end_seconds",
+        "Duration from the beginning of the measurement until the end of the step.",
+        phases.iter().map(|(phase, pair)| {
+            (
+                format!("{labels},phase=\"{phase}\""),
+                DurationPair::total(pair).as_secs_f64(),
+            )
+        }),
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_http_status",
+        "Status code of the final HTTP response.",
+        http_status.map(|status| (labels.clone(), f64::from(status))),
+    );
+    metrics.push_str("# EOF\n");
+    metrics
+}
+
+/// Renders a failed measurement of the given input in the OpenMetrics text
+/// exposition format.
+///
+/// Failures are visible in the same metrics as [`render_openmetrics`] and
+/// [`render_probe_openmetrics`]. The
+/// metrics carry the labels `target` and `protocol`:
+/// - `ttfb_success`: always `0`
+/// - `ttfb_error`: always `1`, with the `kind` label, see [`TtfbError::kind`]
+#[must_use]
+pub fn render_openmetrics_error(input: &str, err: &TtfbError) -> String {
+    let labels = format!(
+        "target=\"{target}\",protocol=\"{protocol}\"",
+        target = escape_label_value(input),
+        protocol = escape_label_value(protocol(input)),
+    );
+    let mut metrics = String::new();
+    write_metric_family(
+        &mut metrics,
+        "ttfb_success",
+        "Whether the measurement succeeded.",
+        [(labels.clone(), 0.0)],
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_error",
+        "The kind of the error of a failed measurement.",
+        [(format!("{labels},kind=\"{}\"", err.kind()), 1.0)],
+    );
+    metrics.push_str("# EOF\n");
+    metrics
+}
+
+/// Renders the aggregate of repeated measurements in the OpenMetrics text
+/// exposition format.
+///
+/// All metrics are gauges and carry the labels `target` and `protocol`:
+/// - `ttfb_measurements`: the number of measurements, with the `result`
+///   label, which is `success` or `failure`
+/// - `ttfb_phase_samples`: the number of measurements that include the step,
+///   with the `phase` label, as of [`render_openmetrics`]
+/// - `ttfb_phase_duration_min_seconds`, `ttfb_phase_duration_mean_seconds`,
+///   and `ttfb_phase_duration_max_seconds`: the minimum, mean, and maximum of
+///   the relative duration of the step, with the `phase` label
+#[must_use]
+pub fn render_stats_openmetrics(stats: &TtfbStats) -> String {
+    let labels = format!(
+        "target=\"{target}\",protocol=\"{protocol}\"",
+        target = escape_label_value(stats.target()),
+        protocol = escape_label_value(protocol(stats.target())),
+    );
+    let phase_samples = |value: fn(&PhaseStats) -> f64| {
+        stats
+            .phases()
+            .map(|(phase, phase_stats)| (format!("{labels},phase=\"{phase}\""), value(phase_stats)))
+            .collect::<Vec<_>>()
+    };
+
+    let mut metrics = String::new();
+    write_metric_family(
+        &mut metrics,
+        "ttfb_measurements",
+        "Number of measurements by result.",
+        [
+            (
+                format!("{labels},result=\"success\""),
+                stats.successes() as f64,
+            ),
+            (
+                format!("{labels},result=\"failure\""),
+                stats.failures() as f64,
+            ),
+        ],
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_samples",
+        "Number of successful measurements that include the step.",
+        phase_samples(|phase_stats| phase_stats.count() as f64),
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_duration_min_seconds",
+        "Minimum duration of the step of the measurements.",
+        phase_samples(|phase_stats| phase_stats.min().as_secs_f64()),
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_duration_mean_seconds",
+        "Mean duration of the step of the measurements.",
+        phase_samples(|phase_stats| phase_stats.mean().as_secs_f64()),
+    );
+    write_metric_family(
+        &mut metrics,
+        "ttfb_phase_duration_max_seconds",
+        "Maximum duration of the step of the measurements.",
+        phase_samples(|phase_stats| phase_stats.max().as_secs_f64()),
+    );
+    metrics.push_str("# EOF\n");
+    metrics
+}
+
+/// Appends the metadata and the samples (labels and value) of a gauge. Does
+/// nothing without samples.
+fn write_metric_family(
+    metrics: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    let mut samples = samples.into_iter().peekable();
+    if samples.peek().is_none() {
+        return;
+    }
+    // Writing to a String can't fail.
+    let _ = writeln!(metrics, "# TYPE {name} gauge");
+    if name.ends_with("_seconds") {
+        let _ = writeln!(metrics, "# UNIT {name} seconds");
+    }
+    let _ = writeln!(metrics, "# HELP {name} {help}");
+    for (labels, value) in samples {
+        let _ = writeln!(metrics, "{name}{{{labels}}} {value}");
+    }
+}
+
+/// Returns the scheme of the input, which defaults to `http`.
+fn protocol(input: &str) -> &str {
+    input.split_once("://").map_or("http", |(scheme, _)| scheme)
+}
+
+/// Escapes backslashes, double quotes, and line breaks in label values.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InvalidUrlError;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    #[test]
+    fn render_outcome() {
+        let connection = ConnectionInfo {
+            user_input: "https://phip1611.de".to_string(),
+            ip_addr: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+            port: 443,
+            dns_duration_rel: None,
+            tcp_connect_duration_rel: Duration::from_millis(2),
+            starttls: None,
+            tls_handshake_duration_rel: Some(Duration::from_millis(3)),
+            tls_handshake_timings: None,
+            tls_info: None,
+            https_record: None,
+        };
+        let outcome = TtfbOutcome::new(
+            connection,
+            Duration::from_millis(4),
+            Duration::from_millis(5),
+            Some(200),
+        );
+        let labels = r#"target="https://phip1611.de",ip="1.2.3.4",protocol="https""#;
+        assert_eq!(
+            render_openmetrics(&outcome),
+            format!(
+                "# TYPE ttfb_success gauge\n\
+                # HELP ttfb_success Whether the measurement succeeded.\n\
+                ttfb_success{{{labels}}} 1\n\
+                # TYPE ttfb_phase_duration_seconds gauge\n\
+                # UNIT ttfb_phase_duration_seconds seconds\n\
+                # HELP ttfb_phase_duration_seconds Duration of the step of the measurement.\n\
+                ttfb_phase_duration_seconds{{{labels},phase=\"tcp_connect\"}} 0.002\n\
+                ttfb_phase_duration_seconds{{{labels},phase=\"tls_handshake\"}} 0.003\n\
+                ttfb_phase_duration_seconds{{{labels},phase=\"request_send\"}} 0.004\n\
+                ttfb_phase_duration_seconds{{{labels},phase=\"ttfb\"}} 0.005\n\
+                # TYPE ttfb_phase_end_seconds gauge\n\
+                # UNIT ttfb_phase_end_seconds seconds\n\
+                # HELP ttfb_phase_end_seconds Duration from the beginning of the measurement until the end of the step.\n\
+                ttfb_phase_end_seconds{{{labels},phase=\"tcp_connect\"}} 0.002\n\
+                ttfb_phase_end_seconds{{{labels},phase=\"tls_handshake\"}} 0.005\n\
+                ttfb_phase_end_seconds{{{labels},phase=\"request_send\"}} 0.009\n\
+                ttfb_phase_end_seconds{{{labels},phase=\"ttfb\"}} 0.014\n\
+                # TYPE ttfb_http_status gauge\n\
+                # HELP ttfb_http_status Status code of the final HTTP response.\n\
+                ttfb_http_status{{{labels}}} 200\n\
+                # EOF\n"
+            )
+        );
+    }
+
+    #[test]
+    fn render_stats() {
+        let outcome = |ttfb_millis| {
+            let connection = ConnectionInfo {
+                user_input: "http://phip1611.de".to_string(),
+                ip_addr: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+                port: 80,
+                dns_duration_rel: None,
+                tcp_connect_duration_rel: Duration::from_millis(2),
+                starttls: None,
+                tls_handshake_duration_rel: None,
+                tls_handshake_timings: None,
+                tls_info: None,
+                https_record: None,
+            };
+            Ok(TtfbOutcome::new(
+                connection,
+                Duration::from_millis(1),
+                Duration::from_millis(ttfb_millis),
+                Some(200),
+            ))
+        };
+        let mut stats = TtfbStats::new("http://phip1611.de");
+        stats.add(&outcome(10));
+        stats.add(&outcome(30));
+        stats.add(&Err(TtfbError::NoHttpResponse));
+
+        let labels = r#"target="http://phip1611.de",protocol="http""#;
+        assert_eq!(
+            render_stats_openmetrics(&stats),
+            format!(
+                "# TYPE ttfb_measurements gauge\n\
+                # HELP ttfb_measurements Number of measurements by result.\n\
+                ttfb_measurements{{{labels},result=\"success\"}} 2\n\
+                ttfb_measurements{{{labels},result=\"failure\"}} 1\n\
+                # TYPE ttfb_phase_samples gauge\n\
+                # HELP ttfb_phase_samples Number of successful measurements that include the step.\n\
+                ttfb_phase_samples{{{labels},phase=\"tcp_connect\"}} 2\n\
+                ttfb_phase_samples{{{labels},phase=\"request_send\"}} 2\n\
+                ttfb_phase_samples{{{labels},phase=\"ttfb\"}} 2\n\
+                # TYPE ttfb_phase_duration_min_seconds gauge\n\
+                # UNIT ttfb_phase_duration_min_seconds seconds\n\
+                # HELP ttfb_phase_duration_min_seconds Minimum duration of the step of the measurements.\n\
+                ttfb_phase_duration_min_seconds{{{labels},phase=\"tcp_connect\"}} 0.002\n\
+                ttfb_phase_duration_min_seconds{{{labels},phase=\"request_send\"}} 0.001\n\
+                ttfb_phase_duration_min_seconds{{{labels},phase=\"ttfb\"}} 0.01\n\
+                # TYPE ttfb_phase_duration_mean_seconds gauge\n\
+                # UNIT ttfb_phase_duration_mean_seconds seconds\n\
+                # HELP ttfb_phase_duration_mean_seconds Mean duration of the step of the measurements.\n\
+                ttfb_phase_duration_mean_seconds{{{labels},phase=\"tcp_connect\"}} 0.002\n\
+                ttfb_phase_duration_mean_seconds{{{labels},phase=\"request_send\"}} 0.001\n\
+                ttfb_phase_duration_mean_seconds{{{labels},phase=\"ttfb\"}} 0.02\n\
+                # TYPE ttfb_phase_duration_max_seconds gauge\n\
+                # UNIT ttfb_phase_duration_max_seconds seconds\n\
+                # HELP ttfb_phase_duration_max_seconds Maximum duration of the step of the measurements.\n\
+                ttfb_phase_duration_max_seconds{{{labels},phase=\"tcp_connect\"}} 0.002\n\
+                ttfb_phase_duration_max_seconds{{{labels},phase=\"request_send\"}} 0.001\n\
+                ttfb_phase_duration_max_seconds{{{labels},phase=\"ttfb\"}} 0.03\n\
+                # EOF\n"
+            )
+        );
+    }
+
+    #[test]
+    fn render_error() {
+        let err = TtfbError::InvalidUrl(InvalidUrlError::MissingPort);
+        assert_eq!(
+            render_openmetrics_error("tcp://a\"b", &err),
+            "# TYPE ttfb_success gauge\n\
+            # HELP ttfb_success Whether the measurement succeeded.\n\
+            ttfb_success{target=\"tcp://a\\\"b\",protocol=\"tcp\"} 0\n\
+            # TYPE ttfb_error gauge\n\
+            # HELP ttfb_error The kind of the error of a failed measurement.\n\
+            ttfb_error{target=\"tcp://a\\\"b\",protocol=\"tcp\",kind=\"InvalidUrl\"} 1\n\
+            # EOF\n"
+        );
+    }
+}
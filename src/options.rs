@@ -0,0 +1,279 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`TtfbOptions`].
+
+use derive_more::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options for [`ttfb_with_options`](crate::ttfb_with_options).
+///
+/// The default value corresponds to a plain [`ttfb`](crate::ttfb) call that
+/// doesn't accept insecure certificates.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TtfbOptions {
+    /// If illegal certificates (untrusted, expired) should be accepted when
+    /// https is used. Similar to `-k/--insecure` in `curl`.
+    pub allow_insecure_certificates: bool,
+    /// Options for the TLS handshake.
+    pub tls: TlsOptions,
+    /// Options for the HTTP request.
+    pub http: HttpOptions,
+    /// Options for the measurements after the upgrade of `ws://` and
+    /// `wss://` URLs.
+    pub websocket: WebSocketOptions,
+    /// Options for the probes of `tcp://` and `tls://` URLs.
+    pub probe: ProbeOptions,
+}
+
+/// HTTP-specific part of [`TtfbOptions`].
+///
+/// By default, a `GET` request without a body and with the headers `Host`,
+/// `User-Agent`, `Accept`, and `Accept-Encoding` is sent.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HttpOptions {
+    /// The request method, such as `POST`. Defaults to `POST` if a
+    /// [`Self::body`] is given, and to `GET` otherwise.
+    pub method: Option<String>,
+    /// Additional request headers as pairs of name and value. A header with
+    /// the same name (case-insensitive) as a default header replaces it. A
+    /// header without a value removes the default header of that name.
+    pub headers: Vec<(String, Option<String>)>,
+    /// The request body. If given, a `Content-Length` header is added.
+    pub body: Option<Vec<u8>>,
+    /// Whether an `Expect: 100-continue` header should be sent with the
+    /// [`Self::body`]. Then, the body is only sent after the server responded
+    /// with `100 Continue`, or after one second without a response (as
+    /// `curl` does it). If the server responds with a final status instead,
+    /// the body isn't sent at all.
+    ///
+    /// The results are available via
+    /// [`TtfbOutcome::http_upload`](crate::TtfbOutcome::http_upload).
+    pub expect_continue: bool,
+    /// If set, the response body is read after the head and the arrival of
+    /// its chunks is recorded. This reveals stalls of streaming endpoints,
+    /// such as chunked JSON or LLM token streams.
+    ///
+    /// The results are available via
+    /// [`TtfbOutcome::http_body`](crate::TtfbOutcome::http_body).
+    pub stream: Option<StreamOptions>,
+}
+
+/// Options for reading the response body, see [`HttpOptions::stream`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StreamOptions {
+    /// Stops reading, if no data arrived for this duration. By default, the
+    /// body is read until it is complete or the connection is closed.
+    pub idle_timeout: Option<Duration>,
+    /// Stops reading after this duration since the first byte of the
+    /// response, which is necessary for endless streams. By default, the body
+    /// is read until it is complete or the connection is closed.
+    pub window: Option<Duration>,
+    /// Whether the body is a `text/event-stream` (Server-Sent Events) that is
+    /// parsed into events. This also replaces the default `Accept` header by
+    /// `Accept: text/event-stream` and omits the default `Accept-Encoding`
+    /// header, as compressed streams can't be parsed.
+    ///
+    /// The events are available via
+    /// [`HttpBody::events`](crate::HttpBody::events).
+    pub event_stream: bool,
+}
+
+/// WebSocket-specific part of [`TtfbOptions`]. Only relevant for `ws` and
+/// `wss` URLs.
+///
+/// The results are available via
+/// [`TtfbOutcome::websocket`](crate::TtfbOutcome::websocket).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WebSocketOptions {
+    /// Whether a ping should be sent after the upgrade to measure the round
+    /// trip until the matching pong.
+    pub ping: bool,
+    /// Whether to wait for the first (text or binary) message of the server.
+    pub first_message: bool,
+    /// How long to wait for the pong and for the first message, counted from
+    /// the upgrade. The limit applies to the whole measurement, even if the
+    /// server keeps sending other frames. By default, there is no limit.
+    pub timeout: Option<Duration>,
+}
+
+/// Probe-specific part of [`TtfbOptions`]. Only relevant for `tcp` and `tls`
+/// URLs.
+///
+/// By default, the probe stops after the TCP connect or the TLS handshake.
+/// The first byte that the server sends back is reported as
+/// [`TtfbOutcome::ttfb_duration`](crate::TtfbOutcome::ttfb_duration).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ProbeOptions {
+    /// Payload that is sent after the connect or the handshake. Then, the
+    /// first byte of the response is awaited.
+    pub payload: Option<Vec<u8>>,
+    /// Whether to wait for the first byte of the server without sending a
+    /// payload, as for protocols in which the server speaks first (banner),
+    /// such as SMTP or SSH.
+    pub banner: bool,
+    /// How long to wait for the first byte. By default, there is no limit.
+    pub timeout: Option<Duration>,
+}
+
+impl HttpOptions {
+    /// Returns the request method that is sent, i.e., [`Self::method`] or
+    /// the default that depends on [`Self::body`].
+    #[must_use]
+    pub fn effective_method(&self) -> &str {
+        match (&self.method, &self.body) {
+            (Some(method), _) => method,
+            (None, Some(_)) => "POST",
+            (None, None) => "GET",
+        }
+    }
+}
+
+/// TLS implementation that performs the handshake. Which backends are
+/// available depends on the enabled cargo features.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+pub enum TlsBackend {
+    /// rustls with `aws-lc-rs` as crypto provider, if the `aws-lc-rs` feature
+    /// is enabled, and `ring` otherwise. This supports all [`TlsOptions`].
+    #[cfg(feature = "rustls")]
+    #[display("rustls")]
+    Rustls,
+    /// The TLS implementation of the platform (OpenSSL on Linux, Secure
+    /// Transport on macOS, SChannel on Windows). This only supports
+    /// [`TlsOptions::version`] and [`TlsOptions::sni`]. Other options fail
+    /// with
+    /// [`TtfbError::InvalidTlsOptions`](crate::TtfbError::InvalidTlsOptions),
+    /// and the `SSLKEYLOGFILE` environment variable is ignored. Requires the
+    /// `native-tls` feature.
+    ///
+    /// The [`TlsInfo`](crate::TlsInfo) is incomplete: the protocol version
+    /// and the cipher suite are `unknown`, there is no key-exchange group
+    /// and no [`TlsHandshakeTimings`](crate::TlsHandshakeTimings), and only
+    /// the leaf certificate is reported. With
+    /// [`TtfbOptions::allow_insecure_certificates`](crate::TtfbOptions::allow_insecure_certificates),
+    /// the verification is skipped entirely, so
+    /// [`TlsInfo::verification_error`](crate::TlsInfo::verification_error)
+    /// is always `None`.
+    #[cfg(feature = "native-tls")]
+    #[display("native-tls")]
+    NativeTls,
+}
+
+#[cfg(feature = "rustls")]
+const DEFAULT_TLS_BACKEND: Option<TlsBackend> = Some(TlsBackend::Rustls);
+#[cfg(all(not(feature = "rustls"), feature = "native-tls"))]
+const DEFAULT_TLS_BACKEND: Option<TlsBackend> = Some(TlsBackend::NativeTls);
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+const DEFAULT_TLS_BACKEND: Option<TlsBackend> = None;
+
+impl TlsBackend {
+    /// Returns the backend that is used, if [`TlsOptions::backend`] is
+    /// `None`: rustls, if available, and native-tls otherwise. `None`, if
+    /// this crate was built without TLS support.
+    #[must_use]
+    pub const fn default_backend() -> Option<Self> {
+        DEFAULT_TLS_BACKEND
+    }
+}
+
+/// TLS-specific part of [`TtfbOptions`]. Only relevant for `https`, `wss`,
+/// and `tls` URLs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TlsOptions {
+    /// The TLS backend. By default, [`TlsBackend::default_backend`] is used.
+    pub backend: Option<TlsBackend>,
+    /// Whether a second connection should be established after the regular
+    /// measurement to measure the handshake of a resumed TLS session, as it
+    /// would be done by a returning visitor. For TLS 1.3, the HTTP request
+    /// is sent as early data (0-RTT), if the server allows that. This isn't
+    /// supported for `tls` URLs.
+    ///
+    /// The results are available via
+    /// [`TtfbOutcome::tls_resumption`](crate::TtfbOutcome::tls_resumption).
+    pub resumption: bool,
+    /// Pins the TLS version. By default, TLS 1.2 and TLS 1.3 are offered.
+    pub version: Option<TlsVersion>,
+    /// Restricts the offered cipher suites to the given names, such as
+    /// `TLS13_AES_128_GCM_SHA256` or `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`.
+    /// The names are case-insensitive. By default, all supported cipher
+    /// suites are offered.
+    pub cipher_suites: Vec<String>,
+    /// Restricts the offered key-exchange groups to the given names, such as
+    /// `X25519` or `secp256r1`. The names are case-insensitive. By default,
+    /// all supported key-exchange groups are offered.
+    pub kx_groups: Vec<String>,
+    /// Whether the post-quantum hybrid key-exchange group `X25519MLKEM768`
+    /// should be preferred, so that its key share is sent in the
+    /// `ClientHello`. This makes the `ClientHello` larger by more than 1 KiB.
+    /// Fails, if [`TlsOptions::kx_groups`] is set but doesn't contain it.
+    ///
+    /// Requires the `aws-lc-rs` feature, as `ring` doesn't implement ML-KEM.
+    /// The negotiated group is reported via
+    /// [`TlsInfo::key_exchange_group`](crate::TlsInfo::key_exchange_group).
+    pub post_quantum: bool,
+    /// Whether Encrypted Client Hello (ECH) should be used. The ECH configs
+    /// are taken from the HTTPS DNS record of the host, which is looked up in
+    /// an additional DNS query. If the host doesn't publish ECH configs, a
+    /// regular handshake is done.
+    ///
+    /// Requires the `aws-lc-rs` feature and TLS 1.3. The result is reported
+    /// via [`TtfbOutcome::https_record`](crate::TtfbOutcome::https_record)
+    /// and [`TlsInfo::ech_accepted`](crate::TlsInfo::ech_accepted).
+    pub ech: bool,
+    /// The Server Name Indication (SNI) to send.
+    pub sni: Sni,
+    /// File to which the TLS session secrets are appended in the NSS key log
+    /// format, so that tools such as Wireshark can decrypt captured traffic.
+    /// If this is `None`, the file from the `SSLKEYLOGFILE` environment
+    /// variable is used, if it is set.
+    pub key_log_file: Option<PathBuf>,
+}
+
+/// Server Name Indication (SNI) that is sent in the TLS `ClientHello`.
+///
+/// This is independent of the IP address that is connected to, which allows
+/// testing the virtual-host routing of CDNs and origin servers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Sni {
+    /// Use the host of the URL. For IP addresses, no SNI is sent.
+    #[default]
+    FromUrl,
+    /// Send the given name. The certificate of the server is verified against
+    /// this name.
+    Custom(String),
+    /// Don't send SNI at all. The certificate of the server is still verified
+    /// against the host of the URL.
+    Disabled,
+}
+
+/// TLS protocol version that can be pinned via [`TlsOptions::version`].
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+pub enum TlsVersion {
+    /// TLS 1.2
+    #[display("TLSv1.2")]
+    Tls12,
+    /// TLS 1.3
+    #[display("TLSv1.3")]
+    Tls13,
+}
@@ -21,14 +21,21 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
-//! Module for [`TtfbOutcome`].
+//! Module for [`TtfbOutcome`], [`ProbeOutcome`], and the [`ConnectionInfo`]
+//! that they share.
 
+use crate::starttls::StartTls;
+use crate::{
+    HttpBody, HttpHead, HttpUpload, HttpsRecord, InformationalResponse, StartTlsProtocol,
+    TlsHandshakeTimings, TlsInfo, TlsResumption, WebSocketInfo,
+};
 use std::net::IpAddr;
 use std::time::Duration;
 
 /// Bundles the duration of a measurement step with the total duration since
 /// the beginning of the overall measurement.
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DurationPair {
     rel: Duration,
     total: Duration,
@@ -36,7 +43,7 @@ pub struct DurationPair {
 
 impl DurationPair {
     #[allow(clippy::missing_const_for_fn)] // MSRV blocker
-    fn new(duration_step: Duration, absolute_duration_so_far: Duration) -> Self {
+    pub(crate) fn new(duration_step: Duration, absolute_duration_so_far: Duration) -> Self {
         Self {
             rel: duration_step,
             total: absolute_duration_so_far + duration_step,
@@ -57,129 +64,476 @@ impl DurationPair {
     }
 }
 
-/// The final result of this library. It contains all the measured timings.
+/// The measurements of the connection setup, i.e., the DNS lookup, the TCP
+/// connect, and the TLS handshake, which are part of every [`TtfbOutcome`]
+/// and [`ProbeOutcome`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct TtfbOutcome {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectionInfo {
     /// Copy of the user input.
-    user_input: String,
+    pub(crate) user_input: String,
     /// The used IP address (resolved by DNS).
-    ip_addr: IpAddr,
+    pub(crate) ip_addr: IpAddr,
     /// The port.
-    port: u16,
+    pub(crate) port: u16,
     /// If DNS was required, the relative duration of this operation.
-    dns_duration_rel: Option<Duration>,
+    pub(crate) dns_duration_rel: Option<Duration>,
     /// Relative duration of the TCP connection start.
-    tcp_connect_duration_rel: Duration,
-    /// If https is used, the relative duration of the TLS handshake.
-    tls_handshake_duration_rel: Option<Duration>,
+    pub(crate) tcp_connect_duration_rel: Duration,
+    /// For the STARTTLS schemes, the banner and the negotiation before the
+    /// TLS handshake.
+    pub(crate) starttls: Option<StartTls>,
+    /// If TLS is used, the relative duration of the TLS handshake.
+    pub(crate) tls_handshake_duration_rel: Option<Duration>,
+    /// If TLS is used, the breakdown of the TLS handshake duration.
+    pub(crate) tls_handshake_timings: Option<TlsHandshakeTimings>,
+    /// If TLS is used, the negotiated TLS parameters.
+    pub(crate) tls_info: Option<TlsInfo>,
+    /// If requested, the result of the lookup of the HTTPS DNS record.
+    pub(crate) https_record: Option<HttpsRecord>,
+}
+
+impl ConnectionInfo {
+    /// Getter for the provided user input (Host or IP address).
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn user_input(&self) -> &str {
+        &self.user_input
+    }
+
+    /// Getter for `ip_addr` that was used.
+    #[must_use]
+    pub const fn ip_addr(&self) -> IpAddr {
+        self.ip_addr
+    }
+
+    /// Getter for `port` that was used.
+    #[must_use]
+    pub const fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the [`DurationPair`] for the DNS step, if DNS lookup was necessary.
+    #[must_use]
+    pub fn dns_lookup_duration(&self) -> Option<DurationPair> {
+        self.dns_duration_rel
+            .map(|d| DurationPair::new(d, Duration::default()))
+    }
+
+    /// Returns the [`DurationPair`] for the establishment of the TCP connection.
+    #[must_use]
+    pub fn tcp_connect_duration(&self) -> DurationPair {
+        let abs_dur_so_far = self.dns_lookup_duration().unwrap_or_default().total();
+        DurationPair::new(self.tcp_connect_duration_rel, abs_dur_so_far)
+    }
+
+    /// Returns the protocol of the STARTTLS schemes, such as `smtp://`.
+    #[must_use]
+    pub fn starttls_protocol(&self) -> Option<StartTlsProtocol> {
+        self.starttls.map(|starttls| starttls.protocol)
+    }
+
+    /// Returns the [`DurationPair`] for reading the complete banner of the
+    /// server after the TCP connect, for the STARTTLS schemes. `None` for
+    /// PostgreSQL, as the server doesn't send a banner.
+    #[must_use]
+    pub fn starttls_banner_duration(&self) -> Option<DurationPair> {
+        self.starttls
+            .and_then(|starttls| starttls.banner_duration)
+            .map(|dur| DurationPair::new(dur, self.tcp_connect_duration().total()))
+    }
+
+    /// Returns the [`DurationPair`] for the STARTTLS negotiation, i.e., from
+    /// sending the first command until the server is ready for the TLS
+    /// handshake, for the STARTTLS schemes.
+    #[must_use]
+    pub fn starttls_negotiation_duration(&self) -> Option<DurationPair> {
+        self.starttls.map(|starttls| {
+            let abs_dur_so_far = self
+                .starttls_banner_duration()
+                .unwrap_or_else(|| self.tcp_connect_duration())
+                .total();
+            DurationPair::new(starttls.negotiation_duration, abs_dur_so_far)
+        })
+    }
+
+    /// Returns the [`DurationPair`] for the TLS handshake, if the TLS handshake was necessary.
+    /// For the STARTTLS schemes, the handshake follows the negotiation.
+    #[must_use]
+    pub fn tls_handshake_duration(&self) -> Option<DurationPair> {
+        self.tls_handshake_duration_rel.map(|dur| {
+            let abs_dur_so_far = self
+                .starttls_negotiation_duration()
+                .unwrap_or_else(|| self.tcp_connect_duration())
+                .total();
+            DurationPair::new(dur, abs_dur_so_far)
+        })
+    }
+
+    /// Returns the total duration of the connection setup, i.e., the end of
+    /// the TLS handshake, if any, or of the TCP connect otherwise.
+    fn setup_duration(&self) -> DurationPair {
+        self.tls_handshake_duration()
+            .unwrap_or_else(|| self.tcp_connect_duration())
+    }
+
+    /// Returns the breakdown of the TLS handshake duration into network round
+    /// trips and local certificate verification, if the TLS handshake was
+    /// necessary.
+    #[must_use]
+    pub const fn tls_handshake_timings(&self) -> Option<&TlsHandshakeTimings> {
+        self.tls_handshake_timings.as_ref()
+    }
+
+    /// Returns the negotiated TLS parameters and the certificate chain of the
+    /// server, if TLS was used.
+    #[must_use]
+    pub const fn tls_info(&self) -> Option<&TlsInfo> {
+        self.tls_info.as_ref()
+    }
+
+    /// Returns the result of the lookup of the HTTPS DNS record, if this was
+    /// requested via [`TlsOptions::ech`](crate::TlsOptions::ech) and the host
+    /// is a domain.
+    #[must_use]
+    pub const fn https_record(&self) -> Option<&HttpsRecord> {
+        self.https_record.as_ref()
+    }
+}
+
+/// The final result of this library. It contains all the measured timings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TtfbOutcome {
+    /// The measurements of the connection setup.
+    pub(crate) connection: ConnectionInfo,
     /// The relative duration of the HTTP GET request sending.
-    http_get_send_duration_rel: Duration,
-    /// The relative duration until the first byte from the HTTP response (the header) was
-    /// received.
-    http_ttfb_duration_rel: Duration,
+    pub(crate) http_get_send_duration_rel: Duration,
+    /// The relative duration until the first byte from the final HTTP response (the header)
+    /// was received.
+    pub(crate) http_ttfb_duration_rel: Duration,
+    /// The status code of the final HTTP response, if it has a valid status
+    /// line.
+    pub(crate) http_status: Option<u16>,
+    /// Informational (1xx) responses before the final HTTP response.
+    pub(crate) informational_responses: Vec<InformationalResponse>,
+    /// If requested, the measurements of a second connection with a resumed
+    /// TLS session.
+    pub(crate) tls_resumption: Option<TlsResumption>,
+    /// If a request body was sent, the measurements of its transmission.
+    pub(crate) http_upload: Option<HttpUpload>,
+    /// If requested, the timeline of the response body.
+    pub(crate) http_body: Option<HttpBody>,
+    /// For `ws` and `wss` URLs, the result of a successful upgrade.
+    pub(crate) websocket: Option<WebSocketInfo>,
+    /// For `grpc` and `grpcs` URLs, the relative duration between the
+    /// response headers and the trailers.
+    pub(crate) grpc_trailers_duration_rel: Option<Duration>,
+    /// The head of the HTTP request as it was sent.
+    pub(crate) http_request_head: Option<HttpHead>,
+    /// The head of the final HTTP response.
+    pub(crate) http_response_head: Option<HttpHead>,
     // http_content_download_duration: Duration,
 }
 
 impl TtfbOutcome {
-    #[allow(clippy::too_many_arguments)]
+    /// Creates the outcome of an HTTP exchange without any of the optional
+    /// measurements. They are set by name, e.g.,
+    /// `TtfbOutcome { http_body, ..TtfbOutcome::new(...) }`.
     pub(crate) const fn new(
-        user_input: String,
-        ip_addr: IpAddr,
-        port: u16,
-        dns_duration_rel: Option<Duration>,
-        tcp_connect_duration_rel: Duration,
-        tls_handshake_duration_rel: Option<Duration>,
+        connection: ConnectionInfo,
         http_get_send_duration_rel: Duration,
         http_ttfb_duration_rel: Duration,
-        // http_content_download_duration: Duration,
+        http_status: Option<u16>,
     ) -> Self {
         Self {
-            user_input,
-            ip_addr,
-            port,
-            dns_duration_rel,
-            tcp_connect_duration_rel,
-            tls_handshake_duration_rel,
+            connection,
             http_get_send_duration_rel,
             http_ttfb_duration_rel,
+            http_status,
+            informational_responses: Vec::new(),
+            tls_resumption: None,
+            http_upload: None,
+            http_body: None,
+            websocket: None,
+            grpc_trailers_duration_rel: None,
+            http_request_head: None,
+            http_response_head: None,
             // http_content_download_duration,
         }
     }
 
+    /// Returns the measurements of the connection setup, which are shared
+    /// with [`ProbeOutcome`]. The getters of this type, such as
+    /// [`Self::tcp_connect_duration`], return the same values.
+    #[must_use]
+    pub const fn connection(&self) -> &ConnectionInfo {
+        &self.connection
+    }
+
     /// Getter for the provided user input (Host or IP address).
     #[must_use]
-    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
     pub fn user_input(&self) -> &str {
-        &self.user_input
+        self.connection.user_input()
     }
 
     /// Getter for `ip_addr` that was used.
     #[must_use]
     pub const fn ip_addr(&self) -> IpAddr {
-        self.ip_addr
+        self.connection.ip_addr()
     }
 
     /// Getter for `port` that was used.
     #[must_use]
     pub const fn port(&self) -> u16 {
-        self.port
+        self.connection.port()
     }
 
     /// Returns the [`DurationPair`] for the DNS step, if DNS lookup was necessary.
     #[must_use]
     pub fn dns_lookup_duration(&self) -> Option<DurationPair> {
-        self.dns_duration_rel
-            .map(|d| DurationPair::new(d, Duration::default()))
+        self.connection.dns_lookup_duration()
     }
 
     /// Returns the [`DurationPair`] for the establishment of the TCP connection.
     #[must_use]
     pub fn tcp_connect_duration(&self) -> DurationPair {
-        let abs_dur_so_far = self.dns_lookup_duration().unwrap_or_default().total();
-        DurationPair::new(self.tcp_connect_duration_rel, abs_dur_so_far)
+        self.connection.tcp_connect_duration()
     }
 
     /// Returns the [`DurationPair`] for the TLS handshake, if the TLS handshake was necessary.
     #[must_use]
     pub fn tls_handshake_duration(&self) -> Option<DurationPair> {
-        self.tls_handshake_duration_rel.map(|dur| {
-            let abs_dur_so_far = self.tcp_connect_duration().total();
-            DurationPair::new(dur, abs_dur_so_far)
-        })
+        self.connection.tls_handshake_duration()
     }
 
-    /// Returns the [`DurationPair`] for the transmission of the HTTP GET request.
+    /// Returns the [`DurationPair`] for the transmission of the HTTP request.
+    /// If a body is sent, this includes the wait for `100 Continue` and the
+    /// upload of the body. See [`Self::http_upload`] for the breakdown.
+    ///
+    /// For `grpc` and `grpcs` URLs, this is the transmission of the HTTP/2
+    /// connection preface and the health check.
     #[must_use]
     pub fn http_get_send_duration(&self) -> DurationPair {
-        let abs_dur_so_far = self.tls_handshake_duration().unwrap_or_default().total();
+        let abs_dur_so_far = self.connection.setup_duration().total();
         DurationPair::new(self.http_get_send_duration_rel, abs_dur_so_far)
     }
 
-    /// Returns the [`DurationPair`] for the time to first byte (TTFB) of the HTTP response.
+    /// Returns the [`DurationPair`] for the time to first byte (TTFB) of the final HTTP
+    /// response. Informational responses, such as `103 Early Hints`, don't count, as
+    /// they would make the server look faster than it is. See
+    /// [`Self::informational_responses`] for them.
+    ///
+    /// For `grpc` and `grpcs` URLs, this is the time to the response headers
+    /// of the health check.
     #[must_use]
     pub fn ttfb_duration(&self) -> DurationPair {
         let abs_dur_so_far = self.http_get_send_duration().total();
         DurationPair::new(self.http_ttfb_duration_rel, abs_dur_so_far)
     }
+
+    /// Returns the [`DurationPair`] between the response headers and the
+    /// trailers that carry the `grpc-status` of the health check, for `grpc`
+    /// and `grpcs` URLs. The serving status is always `SERVING`, as other
+    /// statuses fail with [`GrpcError`](crate::GrpcError).
+    #[must_use]
+    pub fn grpc_trailers_duration(&self) -> Option<DurationPair> {
+        self.grpc_trailers_duration_rel
+            .map(|dur| DurationPair::new(dur, self.ttfb_duration().total()))
+    }
+
+    /// Returns the status code of the final HTTP response, such as `200`.
+    /// For `grpc` and `grpcs` URLs, this is the `:status` of the HTTP/2
+    /// response. `None`, if the response has no valid HTTP/1.x status line.
+    /// The TTFB is measured nevertheless.
+    #[must_use]
+    pub const fn http_status(&self) -> Option<u16> {
+        self.http_status
+    }
+
+    /// Returns the head (request line and headers) of the HTTP request as it
+    /// was sent. `None` for `grpc` and `grpcs` URLs, as they use HTTP/2.
+    #[must_use]
+    pub const fn http_request_head(&self) -> Option<&HttpHead> {
+        self.http_request_head.as_ref()
+    }
+
+    /// Returns the head (status line and headers) of the final HTTP response.
+    /// `None` for `grpc` and `grpcs` URLs, as they use HTTP/2.
+    #[must_use]
+    pub const fn http_response_head(&self) -> Option<&HttpHead> {
+        self.http_response_head.as_ref()
+    }
+
+    /// Returns the informational (1xx) responses, such as `100 Continue` and
+    /// `103 Early Hints`, that the server sent before the final response.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn informational_responses(&self) -> &[InformationalResponse] {
+        &self.informational_responses
+    }
+
+    /// Returns the breakdown of the TLS handshake duration into network round
+    /// trips and local certificate verification, if the TLS handshake was
+    /// necessary.
+    #[must_use]
+    pub const fn tls_handshake_timings(&self) -> Option<&TlsHandshakeTimings> {
+        self.connection.tls_handshake_timings()
+    }
+
+    /// Returns the negotiated TLS parameters and the certificate chain of the
+    /// server, if TLS was used.
+    #[must_use]
+    pub const fn tls_info(&self) -> Option<&TlsInfo> {
+        self.connection.tls_info()
+    }
+
+    /// Returns the measurements of a second connection that resumed the TLS
+    /// session of the first connection, if this was requested via
+    /// [`TlsOptions::resumption`](crate::TlsOptions::resumption).
+    #[must_use]
+    pub const fn tls_resumption(&self) -> Option<&TlsResumption> {
+        self.tls_resumption.as_ref()
+    }
+
+    /// Returns the result of the lookup of the HTTPS DNS record, if this was
+    /// requested via [`TlsOptions::ech`](crate::TlsOptions::ech) and the host
+    /// is a domain.
+    #[must_use]
+    pub const fn https_record(&self) -> Option<&HttpsRecord> {
+        self.connection.https_record()
+    }
+
+    /// Returns the measurements of the transmission of the request body, if
+    /// a body was sent. `None`, if the server responded with a final status
+    /// instead of `100 Continue`, so that the body wasn't sent.
+    #[must_use]
+    pub const fn http_upload(&self) -> Option<&HttpUpload> {
+        self.http_upload.as_ref()
+    }
+
+    /// Returns the timeline of the response body, if this was requested via
+    /// [`HttpOptions::stream`](crate::HttpOptions::stream).
+    #[must_use]
+    pub const fn http_body(&self) -> Option<&HttpBody> {
+        self.http_body.as_ref()
+    }
+
+    /// Returns the result of the WebSocket upgrade of `ws` and `wss` URLs.
+    /// `None` for other URLs, and if the server didn't respond with
+    /// `101 Switching Protocols`, see [`Self::http_status`].
+    #[must_use]
+    pub const fn websocket(&self) -> Option<&WebSocketInfo> {
+        self.websocket.as_ref()
+    }
+}
+
+/// The result of a [`probe`](crate::probe()) of a `tcp`, `tls`, or STARTTLS
+/// URL, which is measured without HTTP.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeOutcome {
+    /// The measurements of the connection setup.
+    connection: ConnectionInfo,
+    /// The relative duration of sending the payload, if one was sent.
+    send_duration_rel: Option<Duration>,
+    /// The relative duration until the first byte of the response, if one
+    /// was awaited and arrived in time.
+    ttfb_duration_rel: Option<Duration>,
+    /// The first bytes of the response.
+    response: Option<Vec<u8>>,
+}
+
+impl ProbeOutcome {
+    pub(crate) const fn new(
+        connection: ConnectionInfo,
+        send_duration_rel: Option<Duration>,
+        ttfb_duration_rel: Option<Duration>,
+        response: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            connection,
+            send_duration_rel,
+            ttfb_duration_rel,
+            response,
+        }
+    }
+
+    /// Returns the measurements of the connection setup, including the
+    /// STARTTLS negotiation.
+    #[must_use]
+    pub const fn connection(&self) -> &ConnectionInfo {
+        &self.connection
+    }
+
+    /// Returns the [`DurationPair`] for the transmission of the
+    /// [`ProbeOptions::payload`](crate::ProbeOptions::payload). `None`
+    /// without a payload.
+    #[must_use]
+    pub fn send_duration(&self) -> Option<DurationPair> {
+        self.send_duration_rel
+            .map(|dur| DurationPair::new(dur, self.connection.setup_duration().total()))
+    }
+
+    /// Returns the [`DurationPair`] for the first byte of the response to the
+    /// payload or of the banner, see [`ProbeOptions`](crate::ProbeOptions).
+    /// `None`, if no response was awaited, or if none arrived in time.
+    #[must_use]
+    pub fn ttfb_duration(&self) -> Option<DurationPair> {
+        self.ttfb_duration_rel.map(|dur| {
+            let abs_dur_so_far = self
+                .send_duration()
+                .unwrap_or_else(|| self.connection.setup_duration())
+                .total();
+            DurationPair::new(dur, abs_dur_so_far)
+        })
+    }
+
+    /// Returns the first bytes of the response, as they arrived in the first
+    /// read (at most 1 KiB), such as the banner of the server. `None`, if no
+    /// response arrived.
+    #[must_use]
+    pub fn response(&self) -> Option<&[u8]> {
+        self.response.as_deref()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::outcome::TtfbOutcome;
+    use crate::StartTlsProtocol;
+    use crate::outcome::{ConnectionInfo, ProbeOutcome, TtfbOutcome};
+    use crate::starttls::StartTls;
     use std::net::{IpAddr, Ipv4Addr};
     use std::time::Duration;
 
+    /// Returns the connection setup with 1 ms DNS lookup, 2 ms TCP connect,
+    /// and the given TLS handshake and STARTTLS negotiation.
+    fn connection(
+        user_input: &str,
+        tls_handshake_duration_rel: Option<Duration>,
+        starttls: Option<StartTls>,
+    ) -> ConnectionInfo {
+        ConnectionInfo {
+            user_input: user_input.to_string(),
+            ip_addr: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            port: 443,
+            dns_duration_rel: Some(Duration::from_millis(1)),
+            tcp_connect_duration_rel: Duration::from_millis(2),
+            starttls,
+            tls_handshake_duration_rel,
+            tls_handshake_timings: None,
+            tls_info: None,
+            https_record: None,
+        }
+    }
+
     #[test]
     fn outcome_durations_are_sane() {
         let outcome = TtfbOutcome::new(
-            "https://phip1611.de".to_string(),
-            IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
-            443,
-            Some(Duration::from_millis(1)),
-            Duration::from_millis(2),
-            Some(Duration::from_millis(3)),
+            connection("https://phip1611.de", Some(Duration::from_millis(3)), None),
             Duration::from_millis(4),
             Duration::from_millis(5),
+            Some(200),
         );
         assert_eq!(
             outcome.dns_lookup_duration().unwrap().total().as_millis(),
@@ -212,4 +566,150 @@ mod tests {
             "Total TTFB: DNS + TCP connect + TLS handshake + HTTP GET send + relative TTFB"
         );
     }
+
+    #[test]
+    fn outcome_durations_without_tls_are_sane() {
+        let outcome = TtfbOutcome::new(
+            connection("http://phip1611.de", None, None),
+            Duration::from_millis(4),
+            Duration::from_millis(5),
+            Some(200),
+        );
+        assert_eq!(
+            outcome.http_get_send_duration().total().as_millis(),
+            1 + 2 + 4,
+            "DNS + TCP connect + HTTP GET send"
+        );
+        assert_eq!(
+            outcome.ttfb_duration().total().as_millis(),
+            1 + 2 + 4 + 5,
+            "Total TTFB: DNS + TCP connect + HTTP GET send + relative TTFB"
+        );
+    }
+
+    #[test]
+    fn outcome_durations_of_probe_without_payload_are_sane() {
+        let outcome = ProbeOutcome::new(
+            connection(
+                "tls://phip1611.de:465",
+                Some(Duration::from_millis(3)),
+                None,
+            ),
+            None,
+            Some(Duration::from_millis(5)),
+            Some(b"220 phip1611.de ESMTP\r\n".to_vec()),
+        );
+        assert!(outcome.send_duration().is_none());
+        assert_eq!(
+            outcome.ttfb_duration().unwrap().total().as_millis(),
+            1 + 2 + 3 + 5,
+            "Banner TTFB: DNS + TCP connect + TLS handshake + relative TTFB"
+        );
+    }
+
+    #[test]
+    fn outcome_durations_of_grpc_health_check_are_sane() {
+        let outcome = TtfbOutcome {
+            grpc_trailers_duration_rel: Some(Duration::from_millis(6)),
+            ..TtfbOutcome::new(
+                connection("grpcs://phip1611.de", Some(Duration::from_millis(3)), None),
+                Duration::from_millis(4),
+                Duration::from_millis(5),
+                Some(200),
+            )
+        };
+        assert_eq!(
+            outcome
+                .grpc_trailers_duration()
+                .unwrap()
+                .total()
+                .as_millis(),
+            1 + 2 + 3 + 4 + 5 + 6,
+            "DNS + TCP connect + TLS handshake + send + response headers + trailers"
+        );
+    }
+
+    #[test]
+    fn outcome_durations_with_starttls_are_sane() {
+        let outcome = ProbeOutcome::new(
+            connection(
+                "smtp://phip1611.de:587",
+                Some(Duration::from_millis(3)),
+                Some(StartTls {
+                    protocol: StartTlsProtocol::Smtp,
+                    banner_duration: Some(Duration::from_millis(10)),
+                    negotiation_duration: Duration::from_millis(20),
+                }),
+            ),
+            None,
+            None,
+            None,
+        );
+        let connection = outcome.connection();
+        assert_eq!(
+            connection
+                .starttls_banner_duration()
+                .unwrap()
+                .total()
+                .as_millis(),
+            1 + 2 + 10,
+            "DNS + TCP connect + banner"
+        );
+        assert_eq!(
+            connection
+                .starttls_negotiation_duration()
+                .unwrap()
+                .total()
+                .as_millis(),
+            1 + 2 + 10 + 20,
+            "DNS + TCP connect + banner + STARTTLS"
+        );
+        assert_eq!(
+            connection
+                .tls_handshake_duration()
+                .unwrap()
+                .total()
+                .as_millis(),
+            1 + 2 + 10 + 20 + 3,
+            "DNS + TCP connect + banner + STARTTLS + TLS handshake"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn outcome_serde_roundtrip() {
+        let mut connection =
+            connection("https://phip1611.de", Some(Duration::from_millis(3)), None);
+        connection.tls_info = Some(crate::TlsInfo::new(
+            "rustls (ring)".to_string(),
+            "TLSv1.3".to_string(),
+            "TLS13_AES_256_GCM_SHA384".to_string(),
+            Some("X25519".to_string()),
+            None,
+            false,
+            Vec::new(),
+            None,
+            None,
+        ));
+        let outcome = TtfbOutcome {
+            informational_responses: vec![crate::InformationalResponse::from_head(
+                103,
+                Duration::from_millis(1),
+                b"HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload\r\n\r\n",
+            )],
+            http_upload: Some(crate::HttpUpload::new(None, Duration::from_millis(1), 42)),
+            http_request_head: Some(crate::HttpHead::parse(
+                b"GET / HTTP/1.1\r\nHost: phip1611.de\r\n\r\n",
+            )),
+            ..TtfbOutcome::new(
+                connection,
+                Duration::from_millis(4),
+                Duration::from_nanos(5_000_001),
+                Some(200),
+            )
+        };
+        let json = serde_json::to_string(&outcome).unwrap();
+        let deserialized: TtfbOutcome = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, outcome);
+    }
 }
@@ -0,0 +1,100 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the probes of `tcp://` and `tls://` URLs, which measure
+//! non-HTTP services, such as databases or message brokers.
+
+use crate::{IoReadAndWrite, ProbeOptions, TtfbError};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Maximum number of bytes of the response that are kept.
+const MAX_RESPONSE_SIZE: usize = 1024;
+
+/// Measurements of [`execute_probe`].
+pub struct ProbeExchange {
+    /// Duration of sending the payload, if one was sent.
+    pub send_duration: Option<Duration>,
+    /// Duration between sending the payload (or the end of the connect or
+    /// handshake) and the first byte of the response, if one was awaited and
+    /// arrived in time.
+    pub ttfb_duration: Option<Duration>,
+    /// The bytes of the first read of the response.
+    pub response: Option<Vec<u8>>,
+}
+
+/// Sends the payload, if any, and waits for the first byte of the response,
+/// if requested via [`ProbeOptions`]. The `socket` is the underlying TCP
+/// socket of `tcp`, which is used for the timeout.
+pub fn execute_probe(
+    tcp: &mut Box<dyn IoReadAndWrite>,
+    socket: &TcpStream,
+    options: &ProbeOptions,
+) -> Result<ProbeExchange, TtfbError> {
+    let send_duration = options
+        .payload
+        .as_ref()
+        .map(|payload| {
+            let now = Instant::now();
+            tcp.write_all(payload)
+                .and_then(|()| tcp.flush())
+                .map_err(TtfbError::OtherStreamError)?;
+            Ok(now.elapsed())
+        })
+        .transpose()?;
+    if options.payload.is_none() && !options.banner {
+        return Ok(ProbeExchange {
+            send_duration,
+            ttfb_duration: None,
+            response: None,
+        });
+    }
+
+    socket
+        .set_read_timeout(options.timeout)
+        .map_err(TtfbError::OtherStreamError)?;
+    let now = Instant::now();
+    let mut buffer = vec![0; MAX_RESPONSE_SIZE];
+    let (ttfb_duration, response) = match tcp.read(&mut buffer) {
+        // The server closed the connection without a response.
+        Ok(0) => (None, None),
+        Ok(size) => {
+            buffer.truncate(size);
+            (Some(now.elapsed()), Some(buffer))
+        }
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            (None, None)
+        }
+        Err(err) => return Err(TtfbError::OtherStreamError(err)),
+    };
+    socket
+        .set_read_timeout(None)
+        .map_err(TtfbError::OtherStreamError)?;
+
+    Ok(ProbeExchange {
+        send_duration,
+        ttfb_duration,
+        response,
+    })
+}
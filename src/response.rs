@@ -0,0 +1,500 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for reading the head of HTTP/1.1 responses and for
+//! [`InformationalResponse`].
+
+use crate::TtfbError;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::time::Duration;
+
+/// Upper bound for the size of a response head, to not read forever from
+/// misbehaving servers.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Connection whose reads are buffered, so that the head of a response can be
+/// read in chunks without losing the bytes that follow it, such as the body
+/// or a WebSocket frame.
+#[derive(Debug)]
+pub struct BufferedStream<T>(BufReader<T>);
+
+impl<T: Read> BufferedStream<T> {
+    pub fn new(inner: T) -> Self {
+        Self(BufReader::new(inner))
+    }
+}
+
+impl<T: Read> Read for BufferedStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<T: Read> BufRead for BufferedStream<T> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt);
+    }
+}
+
+impl<T: Read + Write> Write for BufferedStream<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.get_mut().flush()
+    }
+}
+
+/// Informational (1xx) response that the server sent before the final
+/// response, such as `100 Continue` or `103 Early Hints`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InformationalResponse {
+    /// The status code, such as `103`.
+    status: u16,
+    /// Duration between the begin of sending the request and the first byte
+    /// of this response.
+    duration: Duration,
+    /// Values of the `Link` headers.
+    links: Vec<String>,
+}
+
+impl InformationalResponse {
+    /// Creates the response from its head.
+    pub(crate) fn from_head(status: u16, duration: Duration, head: &[u8]) -> Self {
+        Self {
+            status,
+            duration,
+            links: header_values(head, "Link"),
+        }
+    }
+
+    /// Returns the status code, such as `103`.
+    #[must_use]
+    pub const fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Returns the duration between the begin of sending the request and the
+    /// first byte of this response. The TTFB of the final response is
+    /// measured from the same point in time, i.e., the begin of sending plus
+    /// [`TtfbOutcome::http_get_send_duration`] plus
+    /// [`TtfbOutcome::ttfb_duration`]. Add the absolute duration before the
+    /// request, i.e., `http_get_send_duration().total() -
+    /// http_get_send_duration().relative()`, to get the absolute duration.
+    ///
+    /// [`TtfbOutcome::http_get_send_duration`]: crate::TtfbOutcome::http_get_send_duration
+    /// [`TtfbOutcome::ttfb_duration`]: crate::TtfbOutcome::ttfb_duration
+    #[must_use]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the values of the `Link` headers, such as
+    /// `</style.css>; rel=preload; as=style`. Typically, only
+    /// `103 Early Hints` contains `Link` headers.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+}
+
+/// Returns whether the status code belongs to an informational response,
+/// which is followed by another response. `101 Switching Protocols` is
+/// excluded, as the connection doesn't speak HTTP/1.1 afterwards.
+pub const fn is_informational(status: u16) -> bool {
+    matches!(status, 100 | 102..=199)
+}
+
+/// Returns the (trimmed) values of all headers with the given name
+/// (case-insensitive) of the response head.
+pub fn header_values(head: &[u8], name: &str) -> Vec<String> {
+    String::from_utf8_lossy(head)
+        .lines()
+        // skip the status line
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(header_name, _)| header_name.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string())
+        .collect()
+}
+
+/// How the end of the response body is determined (RFC 9112, section 6.3).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BodyFraming {
+    /// The response has no body.
+    Empty,
+    /// The body has the given number of (remaining) bytes.
+    Length(u64),
+    /// The body uses the chunked transfer encoding.
+    Chunked(ChunkedDecoder),
+    /// The body ends when the server closes the connection.
+    UntilClose,
+}
+
+impl BodyFraming {
+    /// Determines the framing of the body of the response to a request with
+    /// the given method.
+    pub fn from_head(method: &str, status: u16, head: &[u8]) -> Result<Self, TtfbError> {
+        if method.eq_ignore_ascii_case("HEAD") || matches!(status, 100..=199 | 204 | 304) {
+            return Ok(Self::Empty);
+        }
+        let transfer_encoding = header_values(head, "Transfer-Encoding").join(",");
+        if let Some(last) = transfer_encoding.rsplit(',').next() {
+            if last.trim().eq_ignore_ascii_case("chunked") {
+                return Ok(Self::Chunked(ChunkedDecoder::default()));
+            }
+            if !last.trim().is_empty() {
+                return Ok(Self::UntilClose);
+            }
+        }
+        let Some(length) = header_values(head, "Content-Length").into_iter().next() else {
+            return Ok(Self::UntilClose);
+        };
+        length.parse().map(Self::Length).map_err(|_| {
+            TtfbError::InvalidHttpResponse(format!("Invalid Content-Length '{length}'"))
+        })
+    }
+
+    /// Whether the body is complete.
+    pub const fn is_done(&self) -> bool {
+        match self {
+            Self::Empty | Self::Length(0) => true,
+            Self::Chunked(decoder) => decoder.is_done(),
+            Self::Length(_) | Self::UntilClose => false,
+        }
+    }
+
+    /// Returns how many bytes may be read at most, so that nothing after the
+    /// body is read.
+    pub fn read_limit(&self, buffer_size: usize) -> usize {
+        match self {
+            Self::Length(remaining) => usize::try_from(*remaining)
+                .unwrap_or(usize::MAX)
+                .min(buffer_size),
+            _ => buffer_size,
+        }
+    }
+
+    /// Consumes data that was read from the connection and appends the
+    /// contained payload to `payload`.
+    pub fn decode(&mut self, data: &[u8], payload: &mut Vec<u8>) -> Result<(), TtfbError> {
+        match self {
+            Self::Empty => {}
+            Self::Length(remaining) => {
+                *remaining = remaining.saturating_sub(data.len() as u64);
+                payload.extend_from_slice(data);
+            }
+            Self::Chunked(decoder) => decoder.decode(data, payload)?,
+            Self::UntilClose => payload.extend_from_slice(data),
+        }
+        Ok(())
+    }
+}
+
+/// Incremental decoder of the chunked transfer encoding (RFC 9112,
+/// section 7.1). Chunk extensions and trailers are skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChunkedDecoder {
+    state: ChunkedState,
+    /// The line that is currently read, i.e., a chunk size or a trailer.
+    line: Vec<u8>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum ChunkedState {
+    /// Reading the line with the size of the next chunk.
+    #[default]
+    Size,
+    /// Reading the data of a chunk with the given remaining size.
+    Data(u64),
+    /// Reading the line break after the data of a chunk.
+    DataEnd,
+    /// Reading the trailer lines after the last chunk.
+    Trailer,
+    /// The body is complete.
+    Done,
+}
+
+impl ChunkedDecoder {
+    /// Whether the last chunk and the trailer were read.
+    pub const fn is_done(&self) -> bool {
+        matches!(self.state, ChunkedState::Done)
+    }
+
+    /// Consumes data and appends the payload of the chunks to `payload`.
+    pub fn decode(&mut self, mut data: &[u8], payload: &mut Vec<u8>) -> Result<(), TtfbError> {
+        while let Some((&byte, rest)) = data.split_first() {
+            match self.state {
+                ChunkedState::Data(remaining) => {
+                    let len = usize::try_from(remaining)
+                        .unwrap_or(usize::MAX)
+                        .min(data.len());
+                    payload.extend_from_slice(&data[..len]);
+                    data = &data[len..];
+                    self.state = match remaining - len as u64 {
+                        0 => ChunkedState::DataEnd,
+                        remaining => ChunkedState::Data(remaining),
+                    };
+                    continue;
+                }
+                ChunkedState::Done => return Ok(()),
+                _ => {}
+            }
+            data = rest;
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.line).trim().to_string();
+            self.line.clear();
+            self.state = match self.state {
+                ChunkedState::Size => {
+                    // Strip chunk extensions.
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    match u64::from_str_radix(size, 16) {
+                        Ok(0) => ChunkedState::Trailer,
+                        Ok(size) => ChunkedState::Data(size),
+                        Err(_) => {
+                            return Err(TtfbError::InvalidHttpResponse(format!(
+                                "Invalid chunk size '{size}'"
+                            )));
+                        }
+                    }
+                }
+                ChunkedState::DataEnd if line.is_empty() => ChunkedState::Size,
+                ChunkedState::DataEnd => {
+                    return Err(TtfbError::InvalidHttpResponse(
+                        "Missing line break after chunk".to_string(),
+                    ));
+                }
+                ChunkedState::Trailer if line.is_empty() => ChunkedState::Done,
+                state => state,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Reads the remainder of a response head, i.e., the status line and the
+/// headers up to and including the empty line. The first byte was already
+/// read by the caller, as it is used for the time measurement.
+///
+/// The head is read in chunks from the buffer of the reader. Only the bytes
+/// of the head are consumed, the remainder stays in the buffer.
+///
+/// Responses without a valid status line, e.g., of servers that don't speak
+/// HTTP, end after their first line or when the connection is closed, so
+/// that their TTFB is reported nevertheless.
+pub fn read_response_head(reader: &mut impl BufRead, first_byte: u8) -> Result<Vec<u8>, TtfbError> {
+    let mut head = vec![first_byte];
+    let mut first_line_ended = first_byte == b'\n';
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok([]) => return Ok(head),
+            Ok(buf) => buf,
+            Err(err) if err.kind() == ErrorKind::ConnectionReset => return Ok(head),
+            Err(err) => return Err(TtfbError::OtherStreamError(err)),
+        };
+        let read = head.len();
+        head.extend_from_slice(buf);
+        // Only a line break in the new bytes can end the head. The empty line
+        // may start in the last 3 bytes of the previous read, though.
+        let end = (read..head.len())
+            .filter(|&index| head[index] == b'\n')
+            .map(|index| index + 1)
+            .find(|&end| {
+                let head = &head[..end];
+                let is_first_line = !std::mem::replace(&mut first_line_ended, true);
+                head.ends_with(b"\r\n\r\n")
+                    || head.ends_with(b"\n\n")
+                    || (is_first_line && status_code(head).is_err())
+            });
+        if let Some(end) = end {
+            reader.consume(end - read);
+            head.truncate(end);
+            return Ok(head);
+        }
+        let consumed = head.len() - read;
+        reader.consume(consumed);
+        if head.len() >= MAX_HEAD_SIZE {
+            return Err(TtfbError::InvalidHttpResponse(format!(
+                "The response head is larger than {MAX_HEAD_SIZE} bytes"
+            )));
+        }
+    }
+}
+
+/// Returns the status code from the status line of the response head, such
+/// as `100` for `HTTP/1.1 100 Continue`.
+pub fn status_code(head: &[u8]) -> Result<u16, TtfbError> {
+    let status_line = head.split(|&byte| byte == b'\n').next().unwrap_or_default();
+    let status_line = String::from_utf8_lossy(status_line);
+    let mut parts = status_line.trim_end().splitn(3, ' ');
+    let version = parts.next().unwrap_or_default();
+    let code = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/1.") || code.len() != 3 {
+        return Err(TtfbError::InvalidHttpResponse(format!(
+            "Invalid status line '{status_line}'",
+            status_line = status_line.trim_end()
+        )));
+    }
+    code.parse()
+        .map_err(|_| TtfbError::InvalidHttpResponse(format!("Invalid status code '{code}'")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_response_head_stops_after_empty_line() {
+        let mut reader: &[u8] = b"TTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n\r\n";
+        let head = read_response_head(&mut reader, b'H').unwrap();
+        assert_eq!(head, b"HTTP/1.1 100 Continue\r\n\r\n");
+        assert_eq!(reader, b"HTTP/1.1 200 OK\r\n\r\n");
+
+        // The connection was closed.
+        let mut reader: &[u8] = b"TTP/1.1 200 OK\r\n";
+        let head = read_response_head(&mut reader, b'H').unwrap();
+        assert_eq!(head, b"HTTP/1.1 200 OK\r\n");
+
+        // No HTTP response.
+        let mut reader: &[u8] = b"20 localhost ESMTP\r\n250 OK\r\n";
+        let head = read_response_head(&mut reader, b'2').unwrap();
+        assert_eq!(head, b"220 localhost ESMTP\r\n");
+        assert!(status_code(&head).is_err());
+    }
+
+    #[test]
+    fn read_response_head_in_small_chunks() {
+        // The empty line spans several reads.
+        let data: &[u8] = b"TTP/1.1 200 OK\r\nServer: test\r\n\r\nbody";
+        let mut reader = BufReader::with_capacity(3, data);
+        let head = read_response_head(&mut reader, b'H').unwrap();
+        assert_eq!(head, b"HTTP/1.1 200 OK\r\nServer: test\r\n\r\n");
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).unwrap();
+        assert_eq!(body, b"body");
+
+        let mut reader: &[u8] = b"TTP/1.0 200 OK\n\nbody";
+        let head = read_response_head(&mut reader, b'H').unwrap();
+        assert_eq!(head, b"HTTP/1.0 200 OK\n\n");
+        assert_eq!(reader, b"body");
+
+        // The first line is only checked once it ended.
+        let data: &[u8] = b"20 localhost ESMTP\r\n250 OK\r\n";
+        let mut reader = BufReader::with_capacity(1, data);
+        let head = read_response_head(&mut reader, b'2').unwrap();
+        assert_eq!(head, b"220 localhost ESMTP\r\n");
+    }
+
+    #[test]
+    fn informational_response_links() {
+        let head = b"HTTP/1.1 103 Early Hints\r\n\
+            Link: </style.css>; rel=preload; as=style\r\n\
+            X-Foo: bar\r\n\
+            link: </script.js>; rel=preload; as=script\r\n\
+            \r\n";
+        let response = InformationalResponse::from_head(103, Duration::from_millis(1), head);
+        assert_eq!(
+            response.links(),
+            [
+                "</style.css>; rel=preload; as=style",
+                "</script.js>; rel=preload; as=script"
+            ]
+        );
+        assert!(is_informational(100));
+        assert!(is_informational(103));
+        assert!(!is_informational(101));
+        assert!(!is_informational(200));
+    }
+
+    #[test]
+    fn body_framing_from_head() {
+        let framing = |method, status, head: &[u8]| BodyFraming::from_head(method, status, head);
+        assert_eq!(
+            framing("GET", 200, b"HTTP/1.1 200 OK\r\nContent-Length: 42\r\n\r\n"),
+            Ok(BodyFraming::Length(42))
+        );
+        assert_eq!(
+            framing(
+                "HEAD",
+                200,
+                b"HTTP/1.1 200 OK\r\nContent-Length: 42\r\n\r\n"
+            ),
+            Ok(BodyFraming::Empty)
+        );
+        assert_eq!(
+            framing("GET", 204, b"HTTP/1.1 204 No Content\r\n\r\n"),
+            Ok(BodyFraming::Empty)
+        );
+        assert_eq!(
+            framing(
+                "GET",
+                200,
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, Chunked\r\nContent-Length: 42\r\n\r\n"
+            ),
+            Ok(BodyFraming::Chunked(ChunkedDecoder::default()))
+        );
+        assert_eq!(
+            framing("GET", 200, b"HTTP/1.0 200 OK\r\n\r\n"),
+            Ok(BodyFraming::UntilClose)
+        );
+        assert!(framing("GET", 200, b"HTTP/1.1 200 OK\r\nContent-Length: x\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn chunked_decoder() {
+        let body = b"4;ext=1\r\nWiki\r\n7\r\npedia i\r\n0\r\nExpires: never\r\n\r\n";
+        // Feed the body in all possible splits of two parts.
+        for split in 0..body.len() {
+            let mut decoder = ChunkedDecoder::default();
+            let mut payload = Vec::new();
+            decoder.decode(&body[..split], &mut payload).unwrap();
+            assert!(!decoder.is_done());
+            decoder.decode(&body[split..], &mut payload).unwrap();
+            assert!(decoder.is_done());
+            assert_eq!(payload, b"Wikipedia i");
+        }
+
+        let mut decoder = ChunkedDecoder::default();
+        assert!(decoder.decode(b"zz\r\n", &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn status_code_of_status_line() {
+        assert_eq!(status_code(b"HTTP/1.1 100 Continue\r\n\r\n"), Ok(100));
+        assert_eq!(status_code(b"HTTP/1.0 404 Not Found\r\n\r\n"), Ok(404));
+        assert_eq!(status_code(b"HTTP/1.1 204\r\n\r\n"), Ok(204));
+        assert!(status_code(b"SSH-2.0-OpenSSH\r\n\r\n").is_err());
+        assert!(status_code(b"HTTP/1.1 2000 OK\r\n\r\n").is_err());
+        assert!(status_code(b"HTTP/1.1 abc OK\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`Scheme`], the classification of the URLs that can be
+//! measured.
+
+use crate::{InvalidUrlError, StartTlsProtocol, TtfbError};
+
+/// The scheme of a URL, which determines what is measured: an HTTP request
+/// with [`ttfb`](crate::ttfb), or a [`probe`](crate::probe()) without HTTP.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    /// `http://`, which is also the default without a scheme.
+    Http,
+    /// `https://`
+    Https,
+    /// `ws://`, an HTTP request with a WebSocket upgrade.
+    Ws,
+    /// `wss://`, an HTTPS request with a WebSocket upgrade.
+    Wss,
+    /// `grpc://`, a gRPC health check via HTTP/2 with prior knowledge.
+    Grpc,
+    /// `grpcs://`, a gRPC health check via HTTP/2 over TLS.
+    Grpcs,
+    /// `tcp://`, a probe without HTTP.
+    Tcp,
+    /// `tls://`, a probe without HTTP after the TLS handshake.
+    Tls,
+    /// `smtp://`, `imap://`, `pop3://`, and `postgres://`, a probe after the
+    /// STARTTLS negotiation and the TLS handshake.
+    StartTls(StartTlsProtocol),
+}
+
+impl Scheme {
+    /// Returns the scheme of the user input, such as `https://phip1611.de`.
+    /// Without a scheme, this is [`Scheme::Http`], as for [`ttfb`](crate::ttfb).
+    ///
+    /// ## Return value
+    /// The scheme, or [`InvalidUrlError::WrongScheme`] for unsupported
+    /// schemes.
+    pub fn from_input(input: &str) -> Result<Self, TtfbError> {
+        let name = input.split_once("://").map_or("http", |(name, _)| name);
+        Self::from_name(&name.to_ascii_lowercase())
+            .ok_or_else(|| TtfbError::InvalidUrl(InvalidUrlError::WrongScheme(name.to_string())))
+    }
+
+    /// Returns the scheme of the lowercase name of the scheme of a URL.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "http" => Some(Self::Http),
+            "https" => Some(Self::Https),
+            "ws" => Some(Self::Ws),
+            "wss" => Some(Self::Wss),
+            "grpc" => Some(Self::Grpc),
+            "grpcs" => Some(Self::Grpcs),
+            "tcp" => Some(Self::Tcp),
+            "tls" => Some(Self::Tls),
+            _ => StartTlsProtocol::from_scheme(name).map(Self::StartTls),
+        }
+    }
+
+    /// Returns the port that is used, if the URL has no port. `None` for
+    /// `tcp` and `tls`, which require a port.
+    #[must_use]
+    pub const fn default_port(self) -> Option<u16> {
+        match self {
+            Self::Http | Self::Ws | Self::Grpc => Some(80),
+            Self::Https | Self::Wss | Self::Grpcs => Some(443),
+            Self::Tcp | Self::Tls => None,
+            Self::StartTls(protocol) => Some(protocol.default_port()),
+        }
+    }
+
+    /// Returns whether a TLS handshake is done.
+    #[must_use]
+    pub const fn is_tls(self) -> bool {
+        matches!(
+            self,
+            Self::Https | Self::Wss | Self::Grpcs | Self::Tls | Self::StartTls(_)
+        )
+    }
+
+    /// Returns whether the URL is measured with [`probe`](crate::probe())
+    /// instead of [`ttfb`](crate::ttfb), i.e., without HTTP.
+    #[must_use]
+    pub const fn is_probe(self) -> bool {
+        matches!(self, Self::Tcp | Self::Tls | Self::StartTls(_))
+    }
+
+    /// Returns whether the request is a WebSocket upgrade.
+    #[must_use]
+    pub const fn is_websocket(self) -> bool {
+        matches!(self, Self::Ws | Self::Wss)
+    }
+
+    /// Returns whether a gRPC health check is done.
+    #[must_use]
+    pub const fn is_grpc(self) -> bool {
+        matches!(self, Self::Grpc | Self::Grpcs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_from_input() {
+        assert_eq!(Scheme::from_input("phip1611.de"), Ok(Scheme::Http));
+        assert_eq!(Scheme::from_input("HTTPS://phip1611.de"), Ok(Scheme::Https));
+        assert_eq!(
+            Scheme::from_input("postgresql://phip1611.de"),
+            Ok(Scheme::StartTls(StartTlsProtocol::Postgres))
+        );
+        assert_eq!(
+            Scheme::from_input("ftp://phip1611.de"),
+            Err(TtfbError::InvalidUrl(InvalidUrlError::WrongScheme(
+                "ftp".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn scheme_classification() {
+        assert!(Scheme::Grpcs.is_tls() && Scheme::Grpcs.is_grpc());
+        assert!(!Scheme::Ws.is_tls() && Scheme::Ws.is_websocket());
+        assert!(Scheme::StartTls(StartTlsProtocol::Smtp).is_probe());
+        assert!(!Scheme::Https.is_probe());
+        assert_eq!(Scheme::Tcp.default_port(), None);
+        assert_eq!(
+            Scheme::StartTls(StartTlsProtocol::Imap).default_port(),
+            Some(143)
+        );
+    }
+}
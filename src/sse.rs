@@ -0,0 +1,187 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`SseEvent`] and the parsing of event streams.
+
+use std::time::Duration;
+
+/// Event of a `text/event-stream` response (Server-Sent Events), if the body
+/// was parsed via
+/// [`StreamOptions::event_stream`](crate::StreamOptions::event_stream).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SseEvent {
+    /// Duration between sending the request and the arrival of the chunk
+    /// that completed the event.
+    time: Duration,
+    /// The event type.
+    event_type: String,
+    /// The data of the event.
+    data: String,
+    /// The last event ID, if any.
+    id: Option<String>,
+}
+
+impl SseEvent {
+    /// Returns the duration between sending the request and the arrival of
+    /// the chunk that completed the event. This has the same base as
+    /// [`BodyChunk::time`](crate::BodyChunk::time).
+    #[must_use]
+    pub const fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Returns the event type. Defaults to `message`.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    /// Returns the data of the event. Multiple `data` lines are joined by a
+    /// line break.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns the last event ID that was set by this or a previous event.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
+/// Incremental parser of the event-stream format, as specified in the HTML
+/// standard.
+#[derive(Clone, Debug, Default)]
+pub struct SseParser {
+    /// The line that is currently read.
+    line: Vec<u8>,
+    /// Whether the last byte was a CR, so that a following LF is skipped.
+    last_was_cr: bool,
+    /// Whether the first line, which may start with a BOM, was processed.
+    started: bool,
+    /// Type of the current event, if set.
+    event_type: Option<String>,
+    /// Data of the current event, if any.
+    data: Option<String>,
+    /// The last event ID.
+    id: Option<String>,
+}
+
+impl SseParser {
+    /// Consumes payload of the body that arrived at `time` and appends the
+    /// completed events to `events`.
+    pub fn feed(&mut self, payload: &[u8], time: Duration, events: &mut Vec<SseEvent>) {
+        for &byte in payload {
+            let last_was_cr = self.last_was_cr;
+            self.last_was_cr = byte == b'\r';
+            match byte {
+                b'\n' if last_was_cr => {}
+                b'\r' | b'\n' => {
+                    let line = std::mem::take(&mut self.line);
+                    let mut line = String::from_utf8_lossy(&line).into_owned();
+                    if !self.started {
+                        self.started = true;
+                        line = line.strip_prefix('\u{FEFF}').unwrap_or(&line).to_string();
+                    }
+                    if let Some(event) = self.process_line(&line, time) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(byte),
+            }
+        }
+    }
+
+    /// Processes a complete line and returns the event, if the line completed
+    /// one.
+    fn process_line(&mut self, line: &str, time: Duration) -> Option<SseEvent> {
+        if line.is_empty() {
+            let event_type = self.event_type.take();
+            return self.data.take().map(|data| SseEvent {
+                time,
+                event_type: event_type.unwrap_or_else(|| "message".to_string()),
+                data,
+                id: self.id.clone(),
+            });
+        }
+        if line.starts_with(':') {
+            // comment
+            return None;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event_type = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            // `retry` and unknown fields are irrelevant for the measurement.
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_event_stream() {
+        let stream = b"\xEF\xBB\xBF: comment\r\n\
+            data: hello\r\n\r\n\
+            event: update\nid: 1\ndata: a\ndata:b\n\n\
+            data\r\r\
+            id: 2\n\n\
+            retry: 100\n\n";
+        // Feed the stream in all possible splits of two parts.
+        for split in 0..stream.len() {
+            let mut parser = SseParser::default();
+            let mut events = Vec::new();
+            parser.feed(&stream[..split], Duration::from_millis(1), &mut events);
+            parser.feed(&stream[split..], Duration::from_millis(2), &mut events);
+            let events = events
+                .iter()
+                .map(|event| (event.event_type(), event.data(), event.id()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                events,
+                [
+                    ("message", "hello", None),
+                    ("update", "a\nb", Some("1")),
+                    ("message", "", Some("1")),
+                ],
+                "split at {split}"
+            );
+        }
+    }
+}
@@ -0,0 +1,407 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`StartTlsProtocol`] and the STARTTLS negotiation, which
+//! upgrades the plaintext connections of mail and database servers to TLS.
+
+use crate::TtfbError;
+use derive_more::Display;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Maximum length of a line of the server.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// How long to wait for every response of the server during the negotiation,
+/// so that a server that never responds, e.g., as it speaks another protocol,
+/// doesn't block the measurement forever.
+const STARTTLS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tag of the IMAP `STARTTLS` command.
+const IMAP_TAG: &str = "a1";
+
+/// The `SSLRequest` message of PostgreSQL: the length and the request code
+/// `80877103`.
+const POSTGRES_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+
+/// Protocol of a STARTTLS probe, which is selected by the scheme of the URL.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StartTlsProtocol {
+    /// SMTP (`smtp://`), upgraded via `EHLO` and `STARTTLS` (RFC 3207).
+    #[display("SMTP")]
+    Smtp,
+    /// IMAP (`imap://`), upgraded via `STARTTLS` (RFC 3501).
+    #[display("IMAP")]
+    Imap,
+    /// POP3 (`pop3://`), upgraded via `STLS` (RFC 2595).
+    #[display("POP3")]
+    Pop3,
+    /// PostgreSQL (`postgres://` or `postgresql://`), upgraded via the
+    /// `SSLRequest` message. The server doesn't send a banner.
+    #[display("PostgreSQL")]
+    Postgres,
+}
+
+impl StartTlsProtocol {
+    /// Returns the protocol of the scheme of a URL, if any.
+    pub(crate) fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme {
+            "smtp" => Some(Self::Smtp),
+            "imap" => Some(Self::Imap),
+            "pop3" => Some(Self::Pop3),
+            "postgres" | "postgresql" => Some(Self::Postgres),
+            _ => None,
+        }
+    }
+
+    /// Returns the port that is used, if the URL has no port: 25 for SMTP,
+    /// 143 for IMAP, 110 for POP3, and 5432 for PostgreSQL.
+    #[must_use]
+    pub const fn default_port(self) -> u16 {
+        match self {
+            Self::Smtp => 25,
+            Self::Imap => 143,
+            Self::Pop3 => 110,
+            Self::Postgres => 5432,
+        }
+    }
+}
+
+/// Measurements of [`negotiate_starttls`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StartTls {
+    /// The protocol.
+    pub protocol: StartTlsProtocol,
+    /// Duration between the TCP connect and the complete banner of the
+    /// server. `None` for PostgreSQL.
+    pub banner_duration: Option<Duration>,
+    /// Duration of the negotiation, i.e., from sending the first command
+    /// until the server is ready for the TLS handshake.
+    pub negotiation_duration: Duration,
+}
+
+/// Reads the banner of the server and negotiates STARTTLS on the connected
+/// plaintext socket. Afterwards, the TLS handshake can be done on it. Fails,
+/// if the server doesn't respond within [`STARTTLS_TIMEOUT`].
+pub fn negotiate_starttls(
+    tcp: &mut TcpStream,
+    protocol: StartTlsProtocol,
+) -> Result<StartTls, TtfbError> {
+    negotiate_starttls_with_timeout(tcp, protocol, STARTTLS_TIMEOUT)
+}
+
+fn negotiate_starttls_with_timeout(
+    tcp: &mut TcpStream,
+    protocol: StartTlsProtocol,
+    timeout: Duration,
+) -> Result<StartTls, TtfbError> {
+    tcp.set_read_timeout(Some(timeout))
+        .map_err(TtfbError::OtherStreamError)?;
+    let res = negotiate(tcp, protocol).map_err(|err| match err {
+        TtfbError::OtherStreamError(err)
+            if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
+            TtfbError::StartTlsFailed(format!(
+                "The server didn't respond within {} ms",
+                timeout.as_millis()
+            ))
+        }
+        err => err,
+    });
+    tcp.set_read_timeout(None)
+        .map_err(TtfbError::OtherStreamError)?;
+    res
+}
+
+fn negotiate(tcp: &mut TcpStream, protocol: StartTlsProtocol) -> Result<StartTls, TtfbError> {
+    let now = Instant::now();
+    let banner_duration = match protocol {
+        StartTlsProtocol::Smtp => {
+            expect_smtp_reply(tcp, "220")?;
+            Some(now.elapsed())
+        }
+        StartTlsProtocol::Imap => {
+            let line = read_line(tcp)?;
+            if !line.starts_with("* OK") {
+                return Err(unexpected_response(&line));
+            }
+            Some(now.elapsed())
+        }
+        StartTlsProtocol::Pop3 => {
+            expect_pop3_ok(tcp)?;
+            Some(now.elapsed())
+        }
+        StartTlsProtocol::Postgres => None,
+    };
+
+    let now = Instant::now();
+    match protocol {
+        StartTlsProtocol::Smtp => {
+            write(tcp, b"EHLO ttfb\r\n")?;
+            expect_smtp_reply(tcp, "250")?;
+            write(tcp, b"STARTTLS\r\n")?;
+            expect_smtp_reply(tcp, "220")?;
+        }
+        StartTlsProtocol::Imap => {
+            write(tcp, format!("{IMAP_TAG} STARTTLS\r\n").as_bytes())?;
+            // Untagged responses may precede the tagged one.
+            let line = loop {
+                let line = read_line(tcp)?;
+                if line.starts_with(&format!("{IMAP_TAG} ")) {
+                    break line;
+                }
+            };
+            if !line.starts_with(&format!("{IMAP_TAG} OK")) {
+                return Err(unexpected_response(&line));
+            }
+        }
+        StartTlsProtocol::Pop3 => {
+            write(tcp, b"STLS\r\n")?;
+            expect_pop3_ok(tcp)?;
+        }
+        StartTlsProtocol::Postgres => {
+            write(tcp, &POSTGRES_SSL_REQUEST)?;
+            let mut response = [0];
+            tcp.read_exact(&mut response)
+                .map_err(TtfbError::OtherStreamError)?;
+            match response[0] {
+                b'S' => {}
+                b'N' => {
+                    return Err(TtfbError::StartTlsFailed(
+                        "The server doesn't support SSL".to_string(),
+                    ));
+                }
+                byte => return Err(unexpected_response(&byte.escape_ascii().to_string())),
+            }
+        }
+    }
+
+    Ok(StartTls {
+        protocol,
+        banner_duration,
+        negotiation_duration: now.elapsed(),
+    })
+}
+
+fn unexpected_response(response: &str) -> TtfbError {
+    TtfbError::StartTlsFailed(format!("Unexpected response '{response}'"))
+}
+
+fn write(tcp: &mut TcpStream, data: &[u8]) -> Result<(), TtfbError> {
+    tcp.write_all(data)
+        .and_then(|()| tcp.flush())
+        .map_err(TtfbError::OtherStreamError)
+}
+
+/// Reads a line without the line break. The line is read byte by byte, so
+/// that nothing after it is consumed.
+fn read_line(tcp: &mut TcpStream) -> Result<String, TtfbError> {
+    let mut line = Vec::new();
+    let mut byte = [0];
+    while line.len() < MAX_LINE_LENGTH {
+        match tcp.read(&mut byte).map_err(TtfbError::OtherStreamError)? {
+            0 => {
+                return Err(TtfbError::StartTlsFailed(
+                    "The server closed the connection".to_string(),
+                ));
+            }
+            _ if byte[0] == b'\n' => {
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return Ok(String::from_utf8_lossy(&line).into_owned());
+            }
+            _ => line.push(byte[0]),
+        }
+    }
+    Err(TtfbError::StartTlsFailed(
+        "The line is too long".to_string(),
+    ))
+}
+
+/// Reads an SMTP reply, which may span multiple lines, such as
+/// `250-first` and `250 last`, and checks its code.
+fn expect_smtp_reply(tcp: &mut TcpStream, code: &str) -> Result<(), TtfbError> {
+    loop {
+        let line = read_line(tcp)?;
+        if !line.starts_with(code) {
+            return Err(unexpected_response(&line));
+        }
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+fn expect_pop3_ok(tcp: &mut TcpStream) -> Result<(), TtfbError> {
+    let line = read_line(tcp)?;
+    if line.starts_with("+OK") {
+        Ok(())
+    } else {
+        Err(unexpected_response(&line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Runs the emulated server on a local port and negotiates STARTTLS with
+    /// it.
+    fn negotiate_with(
+        protocol: StartTlsProtocol,
+        server: fn(TcpStream, &mut dyn FnMut() -> String),
+    ) -> Result<StartTls, TtfbError> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut read_line = || {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                line
+            };
+            server(stream, &mut read_line);
+        });
+        let mut tcp = TcpStream::connect(addr).unwrap();
+        let result = negotiate_starttls(&mut tcp, protocol);
+        drop(tcp);
+        server.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn negotiate_smtp() {
+        let result = negotiate_with(StartTlsProtocol::Smtp, |mut stream, read_line| {
+            stream
+                .write_all(b"220-mail.example.com ESMTP\r\n220 ready\r\n")
+                .unwrap();
+            assert_eq!(read_line(), "EHLO ttfb\r\n");
+            stream
+                .write_all(b"250-mail.example.com\r\n250-PIPELINING\r\n250 STARTTLS\r\n")
+                .unwrap();
+            assert_eq!(read_line(), "STARTTLS\r\n");
+            stream.write_all(b"220 Go ahead\r\n").unwrap();
+        })
+        .unwrap();
+        assert_eq!(result.protocol, StartTlsProtocol::Smtp);
+        assert!(result.banner_duration.is_some());
+    }
+
+    #[test]
+    fn negotiate_smtp_rejected() {
+        let result = negotiate_with(StartTlsProtocol::Smtp, |mut stream, read_line| {
+            stream.write_all(b"220 ready\r\n").unwrap();
+            read_line();
+            stream.write_all(b"250 mail.example.com\r\n").unwrap();
+            read_line();
+            stream
+                .write_all(b"454 TLS not available due to temporary reason\r\n")
+                .unwrap();
+        });
+        assert_eq!(
+            result,
+            Err(TtfbError::StartTlsFailed(
+                "Unexpected response '454 TLS not available due to temporary reason'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn negotiate_imap() {
+        let result = negotiate_with(StartTlsProtocol::Imap, |mut stream, read_line| {
+            stream.write_all(b"* OK IMAP4rev1 ready\r\n").unwrap();
+            assert_eq!(read_line(), "a1 STARTTLS\r\n");
+            stream
+                .write_all(b"* CAPABILITY IMAP4rev1\r\na1 OK Begin TLS negotiation now\r\n")
+                .unwrap();
+        })
+        .unwrap();
+        assert!(result.banner_duration.is_some());
+    }
+
+    #[test]
+    fn negotiate_pop3() {
+        let result = negotiate_with(StartTlsProtocol::Pop3, |mut stream, read_line| {
+            stream.write_all(b"+OK POP3 ready\r\n").unwrap();
+            assert_eq!(read_line(), "STLS\r\n");
+            stream.write_all(b"+OK Begin TLS negotiation\r\n").unwrap();
+        })
+        .unwrap();
+        assert!(result.banner_duration.is_some());
+    }
+
+    #[test]
+    fn negotiate_postgres() {
+        let server: fn(TcpStream, &mut dyn FnMut() -> String) = |mut stream, _| {
+            let mut request = [0; 8];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request, POSTGRES_SSL_REQUEST);
+            stream.write_all(b"S").unwrap();
+        };
+        let result = negotiate_with(StartTlsProtocol::Postgres, server).unwrap();
+        assert_eq!(result.banner_duration, None);
+
+        let server: fn(TcpStream, &mut dyn FnMut() -> String) = |mut stream, _| {
+            stream.read_exact(&mut [0; 8]).unwrap();
+            stream.write_all(b"N").unwrap();
+        };
+        assert!(negotiate_with(StartTlsProtocol::Postgres, server).is_err());
+    }
+
+    #[test]
+    fn negotiate_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut tcp = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        // The server accepts the connection, but never sends a banner.
+        let (_stream, _) = listener.accept().unwrap();
+        let result = negotiate_starttls_with_timeout(
+            &mut tcp,
+            StartTlsProtocol::Smtp,
+            Duration::from_millis(50),
+        );
+        assert_eq!(
+            result,
+            Err(TtfbError::StartTlsFailed(
+                "The server didn't respond within 50 ms".to_string()
+            ))
+        );
+        assert_eq!(tcp.read_timeout().unwrap(), None);
+    }
+
+    #[test]
+    fn protocol_from_scheme() {
+        assert_eq!(
+            StartTlsProtocol::from_scheme("postgresql"),
+            Some(StartTlsProtocol::Postgres)
+        );
+        assert_eq!(StartTlsProtocol::from_scheme("https"), None);
+    }
+}
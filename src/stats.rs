@@ -0,0 +1,244 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`TtfbStats`], the aggregate of repeated measurements of the
+//! same target.
+
+use crate::{DurationPair, TtfbError, TtfbOutcome};
+use std::time::Duration;
+
+/// Names of the steps of a measurement in their order, as they are used in
+/// the OpenMetrics output.
+const PHASES: [&str; 8] = [
+    "dns_lookup",
+    "tcp_connect",
+    "starttls_banner",
+    "starttls_negotiation",
+    "tls_handshake",
+    "request_send",
+    "ttfb",
+    "grpc_trailers",
+];
+
+/// Minimum, mean, and maximum of the relative duration of a step over the
+/// measurements that include the step.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseStats {
+    /// Number of measurements that include the step.
+    count: usize,
+    /// Shortest duration.
+    min: Duration,
+    /// Longest duration.
+    max: Duration,
+    /// Sum of all durations.
+    sum: Duration,
+}
+
+impl PhaseStats {
+    fn add(&mut self, duration: Duration) {
+        self.min = if self.count == 0 {
+            duration
+        } else {
+            self.min.min(duration)
+        };
+        self.max = self.max.max(duration);
+        self.sum = self.sum.saturating_add(duration);
+        self.count += 1;
+    }
+
+    /// Returns the number of measurements that include the step.
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the shortest duration of the step.
+    #[must_use]
+    pub const fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// Returns the mean duration of the step.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        u32::try_from(self.count)
+            .ok()
+            .and_then(|count| self.sum.checked_div(count))
+            .unwrap_or_default()
+    }
+
+    /// Returns the longest duration of the step.
+    #[must_use]
+    pub const fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+/// Aggregate of repeated measurements of the same target, e.g., to report
+/// the minimum, mean, and maximum of every step of several runs via
+/// [`render_stats_openmetrics`](crate::render_stats_openmetrics).
+///
+/// The statistics cover the relative duration of every step, i.e.,
+/// [`DurationPair::relative`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TtfbStats {
+    /// The measured target, such as the user input.
+    target: String,
+    /// Number of successful measurements.
+    successes: usize,
+    /// Number of failed measurements.
+    failures: usize,
+    /// Statistics of every step, in the order of [`PHASES`].
+    phases: [PhaseStats; PHASES.len()],
+}
+
+impl TtfbStats {
+    /// Creates an empty aggregate for the given target.
+    #[must_use]
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            successes: 0,
+            failures: 0,
+            phases: [PhaseStats::default(); PHASES.len()],
+        }
+    }
+
+    /// Adds the result of a measurement, such as of
+    /// [`ttfb_with_options`](crate::ttfb_with_options). Failed measurements
+    /// are only counted.
+    pub fn add(&mut self, result: &Result<TtfbOutcome, TtfbError>) {
+        let Ok(outcome) = result else {
+            self.failures += 1;
+            return;
+        };
+        self.successes += 1;
+        let connection = outcome.connection();
+        let durations = [
+            connection.dns_lookup_duration(),
+            Some(connection.tcp_connect_duration()),
+            connection.starttls_banner_duration(),
+            connection.starttls_negotiation_duration(),
+            connection.tls_handshake_duration(),
+            Some(outcome.http_get_send_duration()),
+            Some(outcome.ttfb_duration()),
+            outcome.grpc_trailers_duration(),
+        ];
+        for (stats, duration_pair) in self.phases.iter_mut().zip(durations) {
+            if let Some(duration_pair) = duration_pair {
+                stats.add(DurationPair::relative(&duration_pair));
+            }
+        }
+    }
+
+    /// Returns the measured target.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Returns the number of successful measurements.
+    #[must_use]
+    pub const fn successes(&self) -> usize {
+        self.successes
+    }
+
+    /// Returns the number of failed measurements.
+    #[must_use]
+    pub const fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// Returns the statistics of the step with the given name, such as
+    /// `tls_handshake` or `ttfb`. `None`, if no successful measurement
+    /// included the step.
+    #[must_use]
+    pub fn phase(&self, name: &str) -> Option<&PhaseStats> {
+        self.phases()
+            .find(|(phase, _)| *phase == name)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Returns the names and statistics of the steps that were included in at
+    /// least one successful measurement, in their order.
+    pub fn phases(&self) -> impl Iterator<Item = (&'static str, &PhaseStats)> {
+        PHASES
+            .into_iter()
+            .zip(&self.phases)
+            .filter(|(_, stats)| stats.count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConnectionInfo;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn outcome(tcp_connect_millis: u64, ttfb_millis: u64) -> TtfbOutcome {
+        let connection = ConnectionInfo {
+            user_input: "http://phip1611.de".to_string(),
+            ip_addr: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+            port: 80,
+            dns_duration_rel: None,
+            tcp_connect_duration_rel: Duration::from_millis(tcp_connect_millis),
+            starttls: None,
+            tls_handshake_duration_rel: None,
+            tls_handshake_timings: None,
+            tls_info: None,
+            https_record: None,
+        };
+        TtfbOutcome::new(
+            connection,
+            Duration::from_millis(1),
+            Duration::from_millis(ttfb_millis),
+            Some(200),
+        )
+    }
+
+    #[test]
+    fn aggregate() {
+        let mut stats = TtfbStats::new("http://phip1611.de");
+        stats.add(&Ok(outcome(2, 10)));
+        stats.add(&Err(TtfbError::NoHttpResponse));
+        stats.add(&Ok(outcome(4, 30)));
+        stats.add(&Ok(outcome(3, 20)));
+
+        assert_eq!(stats.target(), "http://phip1611.de");
+        assert_eq!(stats.successes(), 3);
+        assert_eq!(stats.failures(), 1);
+        assert_eq!(
+            stats.phases().map(|(phase, _)| phase).collect::<Vec<_>>(),
+            ["tcp_connect", "request_send", "ttfb"]
+        );
+        let ttfb = stats.phase("ttfb").unwrap();
+        assert_eq!(ttfb.count(), 3);
+        assert_eq!(ttfb.min(), Duration::from_millis(10));
+        assert_eq!(ttfb.mean(), Duration::from_millis(20));
+        assert_eq!(ttfb.max(), Duration::from_millis(30));
+        assert_eq!(stats.phase("dns_lookup"), None);
+    }
+}
@@ -0,0 +1,174 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the TLS handshake. The handshake itself is implemented by the
+//! backends that are enabled via cargo features, see [`TlsBackend`].
+
+#[cfg(feature = "native-tls")]
+mod native_tls_backend;
+#[cfg(feature = "rustls")]
+mod rustls_backend;
+
+#[cfg(feature = "rustls")]
+pub use rustls_backend::AllowInvalidCertsVerifier;
+
+use crate::tls_info::{TlsHandshakeTimings, TlsInfo};
+use crate::{IoReadAndWrite, Scheme, TlsBackend, TtfbError, TtfbOptions};
+use std::net::TcpStream;
+use std::time::Duration;
+use url::Url;
+
+/// Measurements and negotiated parameters of a successful TLS handshake.
+#[derive(Debug)]
+pub struct TlsHandshake {
+    /// Duration of the TLS handshake.
+    pub duration: Duration,
+    /// Negotiated TLS parameters.
+    pub info: TlsInfo,
+    /// Breakdown of the duration, if supported by the backend.
+    pub timings: Option<TlsHandshakeTimings>,
+    /// Whether the server accepted the early data (0-RTT).
+    pub early_data_accepted: bool,
+}
+
+/// Everything that is needed to perform TLS handshakes with a server using
+/// the selected [`TlsBackend`].
+#[derive(Debug)]
+pub enum TlsConfig {
+    /// See [`TlsBackend::Rustls`].
+    #[cfg(feature = "rustls")]
+    Rustls(rustls_backend::RustlsConfig),
+    /// See [`TlsBackend::NativeTls`].
+    #[cfg(feature = "native-tls")]
+    NativeTls(native_tls_backend::NativeTlsConfig),
+}
+
+/// Builds the [`TlsConfig`] for the TLS handshake, if the scheme requires
+/// TLS, see [`Scheme::is_tls`].
+/// The same config must be used for all connections that should share TLS
+/// sessions.
+///
+/// The `ech_config_list` from the HTTPS DNS record is used, if
+/// [`TlsOptions::ech`](crate::TlsOptions::ech) is set.
+pub fn tls_config_if_necessary(
+    url: &Url,
+    options: &TtfbOptions,
+    #[cfg_attr(not(feature = "rustls"), allow(unused_variables))] ech_config_list: Option<&[u8]>,
+) -> Result<Option<TlsConfig>, TtfbError> {
+    if !Scheme::from_name(url.scheme()).is_some_and(Scheme::is_tls) {
+        return Ok(None);
+    }
+
+    match options.tls.backend.or(TlsBackend::default_backend()) {
+        #[cfg(feature = "rustls")]
+        Some(TlsBackend::Rustls) => rustls_backend::tls_config(url, options, ech_config_list)
+            .map(TlsConfig::Rustls)
+            .map(Some),
+        #[cfg(feature = "native-tls")]
+        Some(TlsBackend::NativeTls) => native_tls_backend::tls_config(url, options)
+            .map(TlsConfig::NativeTls)
+            .map(Some),
+        None => Err(TtfbError::NoTlsBackend),
+    }
+}
+
+/// If a TLS config is given, this replaces the TCP-Stream with a
+/// `TLS<TCP>`-stream. All data will be encrypted by the selected
+/// [`TlsBackend`]. If TLS is used, it measures the time of the TLS handshake
+/// and collects the negotiated parameters.
+///
+/// The `early_data` is sent as TLS 1.3 early data (0-RTT), if the backend
+/// supports it and the server allows this for the resumed session.
+/// Otherwise, the caller is responsible to send it after the handshake.
+pub fn tls_handshake_if_necessary(
+    tcp: TcpStream,
+    config: Option<&TlsConfig>,
+    #[cfg_attr(not(feature = "rustls"), allow(unused_variables))] early_data: &[u8],
+) -> Result<(Box<dyn IoReadAndWrite>, Option<TlsHandshake>), TtfbError> {
+    match config {
+        None => Ok((Box::new(tcp), None)),
+        #[cfg(feature = "rustls")]
+        Some(TlsConfig::Rustls(config)) => rustls_backend::tls_handshake(tcp, config, early_data)
+            .map(|(stream, tls_handshake)| (stream, Some(tls_handshake))),
+        #[cfg(feature = "native-tls")]
+        Some(TlsConfig::NativeTls(config)) => native_tls_backend::tls_handshake(tcp, config)
+            .map(|(stream, tls_handshake)| (stream, Some(tls_handshake))),
+        #[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+        Some(_) => unreachable!("there is no TlsConfig without a TLS backend"),
+    }
+}
+
+/// Returns the name that is used for the verification of the certificate
+/// and, unless disabled, for SNI, but not for DNS.
+///
+/// This can also be an IP address, because certificates can have the IP
+/// address in the "cert subject alternative name" field.
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+fn server_name<'a>(url: &'a Url, sni: &'a crate::Sni) -> &'a str {
+    match sni {
+        crate::Sni::Custom(name) => name.as_str(),
+        crate::Sni::FromUrl | crate::Sni::Disabled => {
+            let host = url.host_str().unwrap_or("");
+            host.strip_prefix('[')
+                .and_then(|host| host.strip_suffix(']'))
+                .unwrap_or(host)
+        }
+    }
+}
+
+/// Returns the ALPN protocols to offer: `h2` for `grpcs` URLs, as gRPC
+/// requires HTTP/2, and none otherwise.
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+fn alpn_protocols(url: &Url) -> &'static [&'static str] {
+    if Scheme::from_name(url.scheme()) == Some(Scheme::Grpcs) {
+        &["h2"]
+    } else {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_config_is_only_built_for_https() {
+        let options = TtfbOptions::default();
+        let url = Url::parse("http://1.2.3.4").unwrap();
+        assert!(
+            tls_config_if_necessary(&url, &options, None)
+                .unwrap()
+                .is_none()
+        );
+
+        for url in ["https://1.2.3.4", "wss://1.2.3.4"] {
+            let url = Url::parse(url).unwrap();
+            let config = tls_config_if_necessary(&url, &options, None);
+            if TlsBackend::default_backend().is_some() {
+                assert!(config.unwrap().is_some());
+            } else {
+                assert!(matches!(config, Err(TtfbError::NoTlsBackend)));
+            }
+        }
+    }
+}
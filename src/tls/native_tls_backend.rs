@@ -0,0 +1,168 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the TLS handshake via native-tls, i.e., the TLS implementation
+//! of the platform.
+
+use super::{TlsHandshake, alpn_protocols, server_name};
+use crate::tls_info::{CertificateInfo, TlsInfo};
+use crate::{IoReadAndWrite, Sni, TlsOptions, TlsVersion, TtfbError, TtfbOptions};
+use native_tls::{HandshakeError, Protocol, TlsConnector};
+use std::io;
+use std::net::TcpStream;
+use std::time::Instant;
+use url::Url;
+
+/// Everything that is needed to perform TLS handshakes with a server.
+#[derive(Debug)]
+pub struct NativeTlsConfig {
+    connector: TlsConnector,
+    /// The name that is used for the verification of the certificate and,
+    /// if enabled, for SNI.
+    server_name: String,
+}
+
+/// Builds the [`NativeTlsConfig`] for the TLS handshake.
+pub fn tls_config(url: &Url, options: &TtfbOptions) -> Result<NativeTlsConfig, TtfbError> {
+    check_options_are_supported(&options.tls)?;
+
+    let protocol = options.tls.version.map(|version| match version {
+        TlsVersion::Tls12 => Protocol::Tlsv12,
+        TlsVersion::Tls13 => Protocol::Tlsv13,
+    });
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(options.allow_insecure_certificates)
+        .use_sni(options.tls.sni != Sni::Disabled)
+        .min_protocol_version(protocol)
+        .max_protocol_version(protocol)
+        .request_alpns(alpn_protocols(url))
+        .build()
+        .map_err(|err| TtfbError::InvalidTlsOptions(err.to_string()))?;
+    Ok(NativeTlsConfig {
+        connector,
+        server_name: server_name(url, &options.tls.sni).to_string(),
+    })
+}
+
+/// native-tls only offers the common denominator of the TLS implementations
+/// of all platforms. Returns an error for all options that it can't honor.
+/// The `SSLKEYLOGFILE` environment variable is ignored instead, as it is
+/// often set globally and not for this measurement.
+fn check_options_are_supported(options: &TlsOptions) -> Result<(), TtfbError> {
+    let unsupported = [
+        (options.resumption, "TLS session resumption"),
+        (
+            !options.cipher_suites.is_empty(),
+            "restricting the cipher suites",
+        ),
+        (
+            !options.kx_groups.is_empty(),
+            "restricting the key-exchange groups",
+        ),
+        (options.post_quantum, "post-quantum key exchange"),
+        (options.key_log_file.is_some(), "key logging"),
+        (options.ech, "Encrypted Client Hello (ECH)"),
+    ];
+    unsupported
+        .iter()
+        .find(|(requested, _)| *requested)
+        .map_or(Ok(()), |(_, what)| {
+            Err(TtfbError::InvalidTlsOptions(format!(
+                "The native-tls backend doesn't support {what}"
+            )))
+        })
+}
+
+/// Replaces the TCP-Stream with a `TLS<TCP>`-stream. All data will be
+/// encrypted by the TLS implementation of the platform. Measures the time of
+/// the TLS handshake.
+///
+/// native-tls doesn't expose the negotiated parameters except for the ALPN
+/// protocol. Only the leaf certificate is known.
+pub fn tls_handshake(
+    tcp: TcpStream,
+    config: &NativeTlsConfig,
+) -> Result<(Box<dyn IoReadAndWrite>, TlsHandshake), TtfbError> {
+    let now = Instant::now();
+    let stream = config
+        .connector
+        .connect(&config.server_name, tcp)
+        .map_err(|err| match err {
+            HandshakeError::Failure(err) => io::Error::other(err),
+            // Can't happen with a blocking stream.
+            HandshakeError::WouldBlock(_) => io::Error::from(io::ErrorKind::WouldBlock),
+        })
+        .map_err(TtfbError::CantVerifyTls)?;
+    let duration = now.elapsed();
+
+    let peer_certificates = stream
+        .peer_certificate()
+        .ok()
+        .flatten()
+        .and_then(|cert| cert.to_der().ok())
+        .and_then(|der| CertificateInfo::from_der(&der))
+        .into_iter()
+        .collect();
+    let alpn_protocol = stream
+        .negotiated_alpn()
+        .ok()
+        .flatten()
+        .map(|protocol| String::from_utf8_lossy(&protocol).into_owned());
+    let info = TlsInfo::new(
+        "native-tls".to_string(),
+        "unknown".to_string(),
+        "unknown".to_string(),
+        None,
+        alpn_protocol,
+        false,
+        peer_certificates,
+        None,
+        None,
+    );
+
+    let tls_handshake = TlsHandshake {
+        duration,
+        info,
+        timings: None,
+        early_data_accepted: false,
+    };
+    Ok((Box::new(stream), tls_handshake))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_config_rejects_unsupported_options() {
+        let url = Url::parse("https://1.2.3.4").unwrap();
+        let mut options = TtfbOptions::default();
+        assert!(tls_config(&url, &options).is_ok());
+
+        options.tls.resumption = true;
+        assert!(matches!(
+            tls_config(&url, &options),
+            Err(TtfbError::InvalidTlsOptions(_))
+        ));
+    }
+}
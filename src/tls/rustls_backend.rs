@@ -0,0 +1,858 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the TLS handshake via rustls.
+
+#[cfg(not(any(feature = "ring", feature = "aws-lc-rs")))]
+compile_error!("The \"rustls\" feature requires the \"ring\" or \"aws-lc-rs\" feature");
+
+use super::{TlsHandshake, alpn_protocols, server_name};
+use crate::tls_info::{CertificateInfo, TlsHandshakeTimings, TlsInfo};
+use crate::{IoReadAndWrite, Sni, TlsOptions, TlsVersion, TtfbError, TtfbOptions};
+#[cfg(feature = "aws-lc-rs")]
+use rustls::client::EchConfig;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{EchMode, EchStatus, WebPkiServerVerifier};
+use rustls::crypto::CryptoProvider;
+#[cfg(feature = "aws-lc-rs")]
+use rustls::pki_types::EchConfigListBytes;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, Error, HandshakeKind,
+    KeyLog, KeyLogFile, ProtocolVersion, RootCertStore, SignatureScheme, StreamOwned,
+    SupportedProtocolVersion,
+};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write as IoWrite;
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Everything that is needed to perform TLS handshakes with a server.
+#[derive(Debug)]
+pub struct RustlsConfig {
+    /// The rustls config. All connections using the same config share TLS
+    /// sessions.
+    config: Arc<ClientConfig>,
+    /// The name that is used for the verification of the certificate and,
+    /// if enabled, for SNI.
+    server_name: ServerName<'static>,
+    /// The certificate verifier that is used by `config`.
+    verifier: Arc<MeasuringCertVerifier>,
+    /// Duration of loading the trusted root certificates. This happens once
+    /// before the measurement.
+    root_store_load_duration: Duration,
+}
+
+/// Builds the [`RustlsConfig`] for the TLS handshake. The same config must
+/// be used for all connections that should share TLS sessions.
+///
+/// If [`TlsOptions::ech`] is set and an `ech_config_list` is given, ECH is
+/// offered.
+pub fn tls_config(
+    url: &Url,
+    options: &TtfbOptions,
+    ech_config_list: Option<&[u8]>,
+) -> Result<RustlsConfig, TtfbError> {
+    // If this is an IP address, rustls doesn't send SNI.
+    let server_name = server_name(url, &options.tls.sni);
+    let server_name = ServerName::try_from(server_name)
+        .map_err(|err| TtfbError::InvalidTlsOptions(format!("{err}: {server_name}")))?
+        .to_owned();
+
+    let versions: &[&'static SupportedProtocolVersion] = match options.tls.version {
+        None => rustls::DEFAULT_VERSIONS,
+        Some(TlsVersion::Tls12) => &[&rustls::version::TLS12],
+        Some(TlsVersion::Tls13) => &[&rustls::version::TLS13],
+    };
+    let provider = Arc::new(crypto_provider(&options.tls)?);
+    let ech_mode = if options.tls.ech {
+        ech_mode(ech_config_list)?
+    } else {
+        None
+    };
+    let builder = ClientConfig::builder_with_provider(provider.clone());
+    let builder = match ech_mode {
+        Some(_) if options.tls.version == Some(TlsVersion::Tls12) => {
+            return Err(TtfbError::InvalidTlsOptions(
+                "ECH requires TLS 1.3".to_string(),
+            ));
+        }
+        // Implies TLS 1.3.
+        Some(ech_mode) => builder.with_ech(ech_mode),
+        None => builder.with_protocol_versions(versions),
+    }
+    .map_err(|err| TtfbError::InvalidTlsOptions(err.to_string()))?;
+    let now = Instant::now();
+    let root_store = Arc::new(root_cert_store());
+    let root_store_load_duration = now.elapsed();
+    let verifier = Arc::new(MeasuringCertVerifier {
+        inner: WebPkiServerVerifier::builder_with_provider(root_store, provider)
+            .build()
+            .ok(),
+        allow_insecure_certificates: options.allow_insecure_certificates,
+        state: Mutex::default(),
+    });
+    let mut config = builder
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+    config.enable_early_data = options.tls.resumption;
+    config.enable_sni = options.tls.sni != Sni::Disabled;
+    config.alpn_protocols = alpn_protocols(url)
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+    config.key_log = match &options.tls.key_log_file {
+        Some(path) => Arc::new(KeyLogToFile::open(path)?),
+        // Does nothing, if SSLKEYLOGFILE is not set.
+        None => Arc::new(KeyLogFile::new()),
+    };
+    Ok(RustlsConfig {
+        config: Arc::new(config),
+        server_name,
+        verifier,
+        root_store_load_duration,
+    })
+}
+
+/// Returns the [`EchMode`] for the ECH configs from the HTTPS DNS record, if
+/// there are any.
+#[cfg(feature = "aws-lc-rs")]
+fn ech_mode(ech_config_list: Option<&[u8]>) -> Result<Option<EchMode>, TtfbError> {
+    ech_config_list
+        .map(|list| {
+            EchConfig::new(
+                EchConfigListBytes::from(list.to_vec()),
+                rustls::crypto::aws_lc_rs::hpke::ALL_SUPPORTED_SUITES,
+            )
+            .map(EchMode::from)
+            .map_err(|err| TtfbError::InvalidTlsOptions(format!("Unusable ECH config: {err}")))
+        })
+        .transpose()
+}
+
+/// `ring` doesn't implement HPKE, which is required for ECH.
+#[cfg(not(feature = "aws-lc-rs"))]
+fn ech_mode(_ech_config_list: Option<&[u8]>) -> Result<Option<EchMode>, TtfbError> {
+    Err(TtfbError::InvalidTlsOptions(
+        "ECH requires the \"aws-lc-rs\" feature".to_string(),
+    ))
+}
+
+/// [`KeyLog`] that appends the TLS session secrets to a given file in the
+/// NSS key log format. Unlike [`KeyLogFile`], this doesn't depend on the
+/// `SSLKEYLOGFILE` environment variable.
+#[derive(Debug)]
+struct KeyLogToFile(Mutex<File>);
+
+impl KeyLogToFile {
+    fn open(path: &Path) -> Result<Self, TtfbError> {
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map(|file| Self(Mutex::new(file)))
+            .map_err(|err| {
+                TtfbError::InvalidTlsOptions(format!(
+                    "Can't open key log file {}: {err}",
+                    path.display()
+                ))
+            })
+    }
+}
+
+impl KeyLog for KeyLogToFile {
+    fn log(&self, label: &str, client_random: &[u8], secret: &[u8]) {
+        let line = format!("{label} {} {}\n", hex(client_random), hex(secret));
+        // Key logging is a best-effort debugging aid and must not influence
+        // the measurement.
+        let _ = self.0.lock().unwrap().write_all(line.as_bytes());
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Returns the [`CryptoProvider`] with the cipher suites and key-exchange
+/// groups restricted to the ones requested in the [`TlsOptions`].
+///
+/// The provider is backed by `aws-lc-rs`, if the corresponding feature is
+/// enabled, and by `ring` otherwise. With the `fips` feature, only
+/// FIPS-approved algorithms are offered.
+fn crypto_provider(options: &TlsOptions) -> Result<CryptoProvider, TtfbError> {
+    #[cfg(feature = "fips")]
+    let mut provider = rustls::crypto::default_fips_provider();
+    #[cfg(all(feature = "aws-lc-rs", not(feature = "fips")))]
+    let mut provider = rustls::crypto::aws_lc_rs::default_provider();
+    #[cfg(not(feature = "aws-lc-rs"))]
+    let mut provider = rustls::crypto::ring::default_provider();
+
+    if !options.cipher_suites.is_empty() {
+        let supported = provider
+            .cipher_suites
+            .iter()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .collect::<Vec<_>>();
+        check_names_are_supported("cipher suite", &options.cipher_suites, &supported)?;
+        provider.cipher_suites.retain(|suite| {
+            contains_ignore_case(&options.cipher_suites, &format!("{:?}", suite.suite()))
+        });
+    }
+
+    if !options.kx_groups.is_empty() {
+        let supported = provider
+            .kx_groups
+            .iter()
+            .map(|group| format!("{:?}", group.name()))
+            .collect::<Vec<_>>();
+        check_names_are_supported("key-exchange group", &options.kx_groups, &supported)?;
+        provider.kx_groups.retain(|group| {
+            contains_ignore_case(&options.kx_groups, &format!("{:?}", group.name()))
+        });
+    }
+
+    if options.post_quantum {
+        prefer_post_quantum_kx_group(&mut provider, !options.kx_groups.is_empty())?;
+    }
+
+    Ok(provider)
+}
+
+/// Moves the post-quantum hybrid key-exchange group `X25519MLKEM768` to the
+/// front, so that its key share is sent in the `ClientHello`. rustls only
+/// sends a key share for the most preferred group.
+///
+/// If the groups were `restricted` to ones without `X25519MLKEM768`, this
+/// fails instead of adding the group again.
+#[cfg(feature = "aws-lc-rs")]
+fn prefer_post_quantum_kx_group(
+    provider: &mut CryptoProvider,
+    restricted: bool,
+) -> Result<(), TtfbError> {
+    let pq_group = rustls::crypto::aws_lc_rs::kx_group::X25519MLKEM768;
+    let position = provider
+        .kx_groups
+        .iter()
+        .position(|group| group.name() == pq_group.name());
+    match position {
+        Some(position) => {
+            let group = provider.kx_groups.remove(position);
+            provider.kx_groups.insert(0, group);
+        }
+        None if restricted => {
+            return Err(TtfbError::InvalidTlsOptions(format!(
+                "Post-quantum key exchange requires the key-exchange group '{:?}'",
+                pq_group.name()
+            )));
+        }
+        None => provider.kx_groups.insert(0, pq_group),
+    }
+    Ok(())
+}
+
+/// `ring` doesn't implement ML-KEM.
+#[cfg(not(feature = "aws-lc-rs"))]
+fn prefer_post_quantum_kx_group(
+    _provider: &mut CryptoProvider,
+    _restricted: bool,
+) -> Result<(), TtfbError> {
+    Err(TtfbError::InvalidTlsOptions(
+        "Post-quantum key exchange requires the \"aws-lc-rs\" feature".to_string(),
+    ))
+}
+
+/// Checks that every requested name is in the list of supported names.
+fn check_names_are_supported(
+    what: &str,
+    requested: &[String],
+    supported: &[String],
+) -> Result<(), TtfbError> {
+    requested
+        .iter()
+        .find(|name| !contains_ignore_case(supported, name))
+        .map_or(Ok(()), |name| {
+            Err(TtfbError::InvalidTlsOptions(format!(
+                "Unsupported {what} '{name}'. Supported are: {}",
+                supported.join(", ")
+            )))
+        })
+}
+
+fn contains_ignore_case(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+/// Returns the trusted root certificates of the system. Falls back to the
+/// bundled webpki roots, if the system certificates can't be loaded.
+fn root_cert_store() -> RootCertStore {
+    let native_certs = rustls_native_certs::load_native_certs();
+    root_cert_store_from(native_certs.errors.is_empty().then_some(native_certs.certs))
+}
+
+/// Returns the store with the given system certificates. Falls back to the
+/// bundled webpki roots, if there are no usable system certificates, e.g.,
+/// in minimal containers without a CA bundle.
+fn root_cert_store_from(native_certs: Option<Vec<CertificateDer<'static>>>) -> RootCertStore {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_parsable_certificates(native_certs.unwrap_or_default());
+    if root_store.is_empty() {
+        root_store.roots = webpki_roots::TLS_SERVER_ROOTS.to_vec();
+    }
+    root_store
+}
+
+/// Replaces the TCP-Stream with a `TLS<TCP>`-stream. All data will be
+/// encrypted using the TLS-functionality of the crate `rustls`. Measures the
+/// time of the TLS handshake and collects the negotiated parameters.
+///
+/// The `early_data` is sent as TLS 1.3 early data (0-RTT), if the server
+/// allows this for the resumed session. Otherwise, the caller is responsible
+/// to send it after the handshake.
+pub fn tls_handshake(
+    mut tcp: TcpStream,
+    config: &RustlsConfig,
+    early_data: &[u8],
+) -> Result<(Box<dyn IoReadAndWrite>, TlsHandshake), TtfbError> {
+    let mut conn = ClientConnection::new(config.config.clone(), config.server_name.clone())
+        .map_err(|err| io::Error::new(io::ErrorKind::ConnectionAborted, err))
+        .map_err(TtfbError::CantVerifyTls)?;
+    if !early_data.is_empty() {
+        if let Some(mut writer) = conn.early_data() {
+            // Only send the data as early data if it fits entirely. A
+            // truncated request would be useless.
+            if writer.bytes_left() >= early_data.len() {
+                writer
+                    .write_all(early_data)
+                    .map_err(TtfbError::OtherStreamError)?;
+            }
+        }
+    }
+    let stats = drive_handshake(&mut conn, &mut tcp).map_err(TtfbError::CantVerifyTls)?;
+    let verification = config.verifier.take_state();
+    let stream = StreamOwned::new(conn, tcp);
+    let tls_handshake = TlsHandshake {
+        duration: stats.duration,
+        info: collect_tls_info(&stream.conn, &config.config, verification.error),
+        timings: Some(TlsHandshakeTimings::new(
+            stats.duration,
+            stats.server_hello_duration,
+            verification.duration,
+            stats.round_trips,
+            stats.client_hello_size,
+            config.root_store_load_duration,
+        )),
+        early_data_accepted: stream.conn.is_early_data_accepted(),
+    };
+    Ok((Box::new(stream), tls_handshake))
+}
+
+/// Measurements of [`drive_handshake`].
+#[derive(Debug)]
+struct HandshakeStats {
+    /// Total duration of the handshake.
+    duration: Duration,
+    /// Duration until the first bytes of the response of the server (i.e.,
+    /// the `ServerHello`) were received.
+    server_hello_duration: Duration,
+    /// Number of network round trips.
+    round_trips: u32,
+    /// Bytes sent before the first response of the server.
+    client_hello_size: usize,
+}
+
+/// Drives the handshake via the low-level state machine of rustls, so that
+/// the individual flights can be measured.
+fn drive_handshake(conn: &mut ClientConnection, tcp: &mut TcpStream) -> io::Result<HandshakeStats> {
+    let now = Instant::now();
+    let mut server_hello_duration = None;
+    let mut round_trips = 0;
+    let mut client_hello_size = 0;
+    // Whether we sent data since we received data for the last time. The
+    // next read then completes a round trip.
+    let mut sent_flight = false;
+
+    while conn.is_handshaking() {
+        while conn.wants_write() {
+            let written = conn.write_tls(tcp)?;
+            if server_hello_duration.is_none() {
+                client_hello_size += written;
+            }
+            sent_flight = true;
+        }
+        tcp.flush()?;
+        if !conn.is_handshaking() {
+            break;
+        }
+
+        if conn.read_tls(tcp)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "tls handshake eof",
+            ));
+        }
+        server_hello_duration.get_or_insert_with(|| now.elapsed());
+        if sent_flight {
+            round_trips += 1;
+            sent_flight = false;
+        }
+
+        if let Err(err) = conn.process_new_packets() {
+            // Try to send the alert to the server. The original error is
+            // more important than a failure to do so.
+            let _ = conn.write_tls(tcp);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+    }
+    // Send the remaining data, such as the client's Finished message.
+    while conn.wants_write() {
+        conn.write_tls(tcp)?;
+    }
+    tcp.flush()?;
+
+    let duration = now.elapsed();
+    Ok(HandshakeStats {
+        duration,
+        server_hello_duration: server_hello_duration.unwrap_or(duration),
+        round_trips,
+        client_hello_size,
+    })
+}
+
+/// Collects the negotiated parameters of an established TLS connection.
+fn collect_tls_info(
+    conn: &ClientConnection,
+    config: &ClientConfig,
+    verification_error: Option<String>,
+) -> TlsInfo {
+    let backend = match (cfg!(feature = "aws-lc-rs"), config.fips()) {
+        (true, true) => "rustls (aws-lc-rs, FIPS)",
+        (true, false) => "rustls (aws-lc-rs)",
+        (false, _) => "rustls (ring)",
+    };
+    let protocol_version = match conn.protocol_version() {
+        Some(ProtocolVersion::TLSv1_2) => "TLSv1.2".to_string(),
+        Some(ProtocolVersion::TLSv1_3) => "TLSv1.3".to_string(),
+        Some(other) => format!("{other:?}"),
+        None => "unknown".to_string(),
+    };
+    let cipher_suite = conn.negotiated_cipher_suite().map_or_else(
+        || "unknown".to_string(),
+        |suite| format!("{:?}", suite.suite()),
+    );
+    let key_exchange_group = conn
+        .negotiated_key_exchange_group()
+        .map(|group| format!("{:?}", group.name()));
+    let alpn_protocol = conn
+        .alpn_protocol()
+        .map(|proto| String::from_utf8_lossy(proto).into_owned());
+    let resumed = conn.handshake_kind() == Some(HandshakeKind::Resumed);
+    let ech_accepted = match conn.ech_status() {
+        EchStatus::Accepted => Some(true),
+        EchStatus::Rejected => Some(false),
+        EchStatus::NotOffered | EchStatus::Grease | EchStatus::Offered => None,
+    };
+    let peer_certificates = conn
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|cert| CertificateInfo::from_der(cert))
+        .collect();
+
+    TlsInfo::new(
+        backend.to_string(),
+        protocol_version,
+        cipher_suite,
+        key_exchange_group,
+        alpn_protocol,
+        resumed,
+        peer_certificates,
+        verification_error,
+        ech_accepted,
+    )
+}
+
+/// Verifier that measures the time spent on verifying the certificate chain
+/// of the server. If insecure certificates are allowed, it accepts every
+/// certificate, like [`AllowInvalidCertsVerifier`], but records why the
+/// regular verification would have failed.
+#[derive(Debug)]
+struct MeasuringCertVerifier {
+    /// The regular verifier. `None`, if it can't be built, e.g., because
+    /// there are no root certificates.
+    inner: Option<Arc<WebPkiServerVerifier>>,
+    /// Whether errors of the regular verifier are ignored.
+    allow_insecure_certificates: bool,
+    /// The state of the current handshake.
+    state: Mutex<VerificationState>,
+}
+
+/// See [`MeasuringCertVerifier`].
+#[derive(Debug, Default)]
+struct VerificationState {
+    /// Accumulated time spent in the regular verifier.
+    duration: Duration,
+    /// If insecure certificates are allowed, the first error of the regular
+    /// verifier.
+    error: Option<String>,
+}
+
+impl MeasuringCertVerifier {
+    /// Runs the regular verifier and measures the duration. Errors are
+    /// only recorded, if insecure certificates are allowed.
+    fn verify<T>(
+        &self,
+        verify_fn: impl FnOnce(&WebPkiServerVerifier) -> Result<T, Error>,
+        assertion: impl FnOnce() -> T,
+    ) -> Result<T, Error> {
+        let now = Instant::now();
+        let result = self.inner.as_deref().map_or(
+            Err(Error::InvalidCertificate(CertificateError::UnknownIssuer)),
+            verify_fn,
+        );
+        let duration = now.elapsed();
+        let mut state = self.state.lock().unwrap();
+        state.duration += duration;
+        match result {
+            Err(err) if self.allow_insecure_certificates => {
+                state.error.get_or_insert_with(|| err.to_string());
+                drop(state);
+                Ok(assertion())
+            }
+            result => result,
+        }
+    }
+
+    /// Verifies the signature of the handshake. If insecure certificates are
+    /// allowed, schemes that the regular verifier doesn't support are only
+    /// offered to be able to connect at all. A regular client wouldn't have
+    /// negotiated them, so their signatures are accepted without recording
+    /// an error that says nothing about the certificate.
+    fn verify_signature(
+        &self,
+        dss: &DigitallySignedStruct,
+        verify_fn: impl FnOnce(&WebPkiServerVerifier) -> Result<HandshakeSignatureValid, Error>,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        let regular_scheme = self
+            .inner
+            .as_ref()
+            .is_some_and(|inner| inner.supported_verify_schemes().contains(&dss.scheme));
+        if self.allow_insecure_certificates && !regular_scheme {
+            return Ok(HandshakeSignatureValid::assertion());
+        }
+        self.verify(verify_fn, HandshakeSignatureValid::assertion)
+    }
+
+    /// Returns and resets the state of the current handshake.
+    fn take_state(&self) -> VerificationState {
+        std::mem::take(&mut self.state.lock().unwrap())
+    }
+}
+
+impl ServerCertVerifier for MeasuringCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        self.verify(
+            |inner| {
+                inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            },
+            ServerCertVerified::assertion,
+        )
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.verify_signature(dss, |inner| {
+            inner.verify_tls12_signature(message, cert, dss)
+        })
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.verify_signature(dss, |inner| {
+            inner.verify_tls13_signature(message, cert, dss)
+        })
+    }
+
+    /// The schemes of the regular verifier come first, so that servers
+    /// prefer them. If insecure certificates are allowed, or if there is no
+    /// regular verifier, all other schemes follow.
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        let mut schemes = self
+            .inner
+            .as_ref()
+            .map(|inner| inner.supported_verify_schemes())
+            .unwrap_or_default();
+        if self.allow_insecure_certificates || schemes.is_empty() {
+            for scheme in AllowInvalidCertsVerifier.supported_verify_schemes() {
+                if !schemes.contains(&scheme) {
+                    schemes.push(scheme);
+                }
+            }
+        }
+        schemes
+    }
+}
+
+/// Custom verifier that allows invalid certificates.
+#[derive(Debug)]
+pub struct AllowInvalidCertsVerifier;
+
+impl ServerCertVerifier for AllowInvalidCertsVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        // Return a list of all.
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA1,
+            SignatureScheme::ECDSA_SHA1_Legacy,
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+            SignatureScheme::ED448,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_cert_store_falls_back_to_webpki_roots() {
+        use rustls::pki_types::pem::PemObject;
+
+        let webpki_roots = webpki_roots::TLS_SERVER_ROOTS.len();
+        assert_eq!(root_cert_store_from(None).len(), webpki_roots);
+        assert_eq!(root_cert_store_from(Some(Vec::new())).len(), webpki_roots);
+        let unparsable = CertificateDer::from(b"not a certificate".to_vec());
+        assert_eq!(
+            root_cert_store_from(Some(vec![unparsable])).len(),
+            webpki_roots
+        );
+
+        let cert =
+            CertificateDer::from_pem_slice(include_bytes!("testdata/localhost.crt")).unwrap();
+        assert_eq!(root_cert_store_from(Some(vec![cert])).len(), 1);
+    }
+
+    #[test]
+    fn measuring_cert_verifier_prefers_schemes_of_regular_verifier() {
+        let provider = Arc::new(crypto_provider(&TlsOptions::default()).unwrap());
+        let inner = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(root_cert_store_from(None)),
+            provider,
+        )
+        .build()
+        .unwrap();
+        let regular_schemes = inner.supported_verify_schemes();
+        let verifier = |allow_insecure_certificates| MeasuringCertVerifier {
+            inner: Some(inner.clone()),
+            allow_insecure_certificates,
+            state: Mutex::default(),
+        };
+
+        assert_eq!(verifier(false).supported_verify_schemes(), regular_schemes);
+        let schemes = verifier(true).supported_verify_schemes();
+        assert!(schemes.starts_with(&regular_schemes));
+        assert!(schemes.contains(&SignatureScheme::ED448));
+    }
+
+    #[test]
+    fn crypto_provider_restricts_cipher_suites_and_kx_groups() {
+        let options = TlsOptions {
+            cipher_suites: vec!["tls13_aes_128_gcm_sha256".to_string()],
+            kx_groups: vec!["X25519".to_string(), "secp384r1".to_string()],
+            ..Default::default()
+        };
+        let provider = crypto_provider(&options).unwrap();
+        assert_eq!(provider.cipher_suites.len(), 1);
+        assert_eq!(provider.kx_groups.len(), 2);
+    }
+
+    #[test]
+    fn crypto_provider_prefers_post_quantum_kx_group() {
+        let options = TlsOptions {
+            post_quantum: true,
+            ..Default::default()
+        };
+        let provider = crypto_provider(&options);
+        if cfg!(feature = "aws-lc-rs") {
+            let provider = provider.unwrap();
+            assert_eq!(
+                format!("{:?}", provider.kx_groups[0].name()),
+                "X25519MLKEM768"
+            );
+            let pq_groups = provider
+                .kx_groups
+                .iter()
+                .filter(|group| format!("{:?}", group.name()) == "X25519MLKEM768")
+                .count();
+            assert_eq!(pq_groups, 1);
+        } else {
+            assert!(matches!(provider, Err(TtfbError::InvalidTlsOptions(_))));
+        }
+    }
+
+    #[test]
+    fn crypto_provider_keeps_excluded_post_quantum_kx_group_out() {
+        let options = TlsOptions {
+            post_quantum: true,
+            kx_groups: vec!["X25519".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            crypto_provider(&options),
+            Err(TtfbError::InvalidTlsOptions(_))
+        ));
+
+        let options = TlsOptions {
+            post_quantum: true,
+            kx_groups: vec!["X25519".to_string(), "X25519MLKEM768".to_string()],
+            ..Default::default()
+        };
+        let provider = crypto_provider(&options);
+        if cfg!(feature = "aws-lc-rs") {
+            let provider = provider.unwrap();
+            let names = provider
+                .kx_groups
+                .iter()
+                .map(|group| format!("{:?}", group.name()))
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["X25519MLKEM768", "X25519"]);
+        } else {
+            assert!(matches!(provider, Err(TtfbError::InvalidTlsOptions(_))));
+        }
+    }
+
+    #[test]
+    fn tls_config_respects_sni_option() {
+        let url = Url::parse("https://1.2.3.4").unwrap();
+        let mut options = TtfbOptions {
+            allow_insecure_certificates: true,
+            ..Default::default()
+        };
+
+        let config = tls_config(&url, &options, None).unwrap();
+        assert_eq!(config.server_name.to_str(), "1.2.3.4");
+        assert!(config.config.enable_sni);
+
+        options.tls.sni = Sni::Custom("phip1611.de".to_string());
+        let config = tls_config(&url, &options, None).unwrap();
+        assert_eq!(config.server_name.to_str(), "phip1611.de");
+        assert!(config.config.enable_sni);
+
+        options.tls.sni = Sni::Disabled;
+        let config = tls_config(&url, &options, None).unwrap();
+        assert_eq!(config.server_name.to_str(), "1.2.3.4");
+        assert!(!config.config.enable_sni);
+    }
+
+    #[test]
+    fn key_log_to_file_uses_nss_format() {
+        let path = std::env::temp_dir().join(format!("ttfb-keylog-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let key_log = KeyLogToFile::open(&path).unwrap();
+        key_log.log("CLIENT_RANDOM", &[0x00, 0xab], &[0x12, 0xff]);
+        key_log.log("EXPORTER_SECRET", &[0x01], &[0x02]);
+        drop(key_log);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content, "CLIENT_RANDOM 00ab 12ff\nEXPORTER_SECRET 01 02\n");
+    }
+
+    #[test]
+    fn crypto_provider_rejects_unknown_names() {
+        let options = TlsOptions {
+            cipher_suites: vec!["TLS_RSA_WITH_RC4_128_MD5".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            crypto_provider(&options),
+            Err(TtfbError::InvalidTlsOptions(_))
+        ));
+
+        let options = TlsOptions {
+            kx_groups: vec!["ffdhe2048".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            crypto_provider(&options),
+            Err(TtfbError::InvalidTlsOptions(_))
+        ));
+    }
+}
@@ -0,0 +1,494 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`TlsInfo`], [`TlsHandshakeTimings`], and [`TlsResumption`].
+
+use crate::DurationPair;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use x509_parser::{certificate::X509Certificate, extensions::GeneralName, prelude::FromDer};
+
+/// Properties of the TLS session that were negotiated during the handshake.
+///
+/// All values are given in their human-readable form, e.g., `TLSv1.3` or
+/// `TLS13_AES_256_GCM_SHA384`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsInfo {
+    /// TLS backend that performed the handshake.
+    backend: String,
+    /// Negotiated TLS version.
+    protocol_version: String,
+    /// Negotiated cipher suite.
+    cipher_suite: String,
+    /// Negotiated key-exchange group, if any.
+    key_exchange_group: Option<String>,
+    /// Negotiated ALPN protocol, if any.
+    alpn_protocol: Option<String>,
+    /// Whether the session was resumed.
+    resumed: bool,
+    /// The certificate chain presented by the server, starting with the leaf.
+    peer_certificates: Vec<CertificateInfo>,
+    /// If insecure certificates are allowed, the reason why the regular
+    /// verification would have failed.
+    verification_error: Option<String>,
+    /// If ECH was offered, whether the server accepted it.
+    ech_accepted: Option<bool>,
+}
+
+impl TlsInfo {
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(any(feature = "rustls", feature = "native-tls")), allow(dead_code))]
+    pub(crate) const fn new(
+        backend: String,
+        protocol_version: String,
+        cipher_suite: String,
+        key_exchange_group: Option<String>,
+        alpn_protocol: Option<String>,
+        resumed: bool,
+        peer_certificates: Vec<CertificateInfo>,
+        verification_error: Option<String>,
+        ech_accepted: Option<bool>,
+    ) -> Self {
+        Self {
+            backend,
+            protocol_version,
+            cipher_suite,
+            key_exchange_group,
+            alpn_protocol,
+            resumed,
+            peer_certificates,
+            verification_error,
+            ech_accepted,
+        }
+    }
+
+    /// Getter for the TLS backend that performed the handshake, such as
+    /// `rustls (ring)` or `native-tls`. See [`TlsBackend`](crate::TlsBackend).
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Getter for the negotiated TLS version, such as `TLSv1.3`. This is
+    /// `unknown`, if the backend doesn't expose it (native-tls).
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// Getter for the negotiated cipher suite, such as `TLS13_AES_256_GCM_SHA384`.
+    /// This is `unknown`, if the backend doesn't expose it (native-tls).
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn cipher_suite(&self) -> &str {
+        &self.cipher_suite
+    }
+
+    /// Getter for the negotiated key-exchange group, such as `X25519`. Always
+    /// `None` for native-tls, as it doesn't expose it.
+    #[must_use]
+    pub fn key_exchange_group(&self) -> Option<&str> {
+        self.key_exchange_group.as_deref()
+    }
+
+    /// Getter for the negotiated ALPN protocol, such as `http/1.1`.
+    #[must_use]
+    pub fn alpn_protocol(&self) -> Option<&str> {
+        self.alpn_protocol.as_deref()
+    }
+
+    /// Whether the TLS session was resumed instead of doing a full handshake.
+    #[must_use]
+    pub const fn resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// Getter for the certificate chain presented by the server. The first
+    /// element is the leaf certificate. native-tls only exposes the leaf
+    /// certificate.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn peer_certificates(&self) -> &[CertificateInfo] {
+        &self.peer_certificates
+    }
+
+    /// Returns the reason why the verification of the certificate chain would
+    /// have failed, if insecure certificates were allowed (similar to
+    /// `-k/--insecure` in `curl`). Always `None` if insecure certificates
+    /// are not allowed, as the handshake fails in that case.
+    #[must_use]
+    pub fn verification_error(&self) -> Option<&str> {
+        self.verification_error.as_deref()
+    }
+
+    /// Returns whether the server accepted the Encrypted Client Hello (ECH),
+    /// or `None` if ECH wasn't offered. See
+    /// [`TlsOptions::ech`](crate::TlsOptions::ech).
+    ///
+    /// If the server rejects ECH, the handshake usually fails, as the server
+    /// then authenticates with the certificate of the public name of the ECH
+    /// config instead of the one of the host.
+    #[must_use]
+    pub const fn ech_accepted(&self) -> Option<bool> {
+        self.ech_accepted
+    }
+}
+
+/// Details of a single X.509 certificate of the certificate chain presented
+/// by the server.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CertificateInfo {
+    /// Distinguished name of the subject.
+    subject: String,
+    /// Distinguished name of the issuer.
+    issuer: String,
+    /// Subject alternative names (DNS names and IP addresses).
+    subject_alt_names: Vec<String>,
+    /// Begin of the validity period.
+    not_before: SystemTime,
+    /// End of the validity period.
+    not_after: SystemTime,
+}
+
+impl CertificateInfo {
+    /// Parses a DER-encoded X.509 certificate. Returns `None` if the
+    /// certificate can't be parsed.
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub(crate) fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        let subject_alt_names = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .map(format_general_name)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            subject_alt_names,
+            not_before: unix_timestamp_to_system_time(cert.validity().not_before.timestamp()),
+            not_after: unix_timestamp_to_system_time(cert.validity().not_after.timestamp()),
+        })
+    }
+
+    /// Getter for the distinguished name of the subject.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Getter for the distinguished name of the issuer.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Getter for the subject alternative names, such as `phip1611.de` or
+    /// `1.1.1.1`.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // MSRV blocker
+    pub fn subject_alt_names(&self) -> &[String] {
+        &self.subject_alt_names
+    }
+
+    /// Getter for the begin of the validity period.
+    #[must_use]
+    pub const fn not_before(&self) -> SystemTime {
+        self.not_before
+    }
+
+    /// Getter for the end of the validity period.
+    #[must_use]
+    pub const fn not_after(&self) -> SystemTime {
+        self.not_after
+    }
+
+    /// Returns the remaining validity of the certificate, or `None` if the
+    /// certificate is already expired.
+    #[must_use]
+    pub fn remaining_validity(&self) -> Option<Duration> {
+        self.not_after.duration_since(SystemTime::now()).ok()
+    }
+}
+
+/// Breakdown of the duration of the TLS handshake into the time spent
+/// waiting for the network and the time spent for local certificate
+/// verification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsHandshakeTimings {
+    /// Total duration of the handshake.
+    total: Duration,
+    /// Duration from the begin of the handshake until the first bytes of the
+    /// server's response (the `ServerHello`) were received.
+    server_hello_duration: Duration,
+    /// Accumulated duration of the local verification of the certificate
+    /// chain and the handshake signatures.
+    certificate_verification_duration: Duration,
+    /// Number of network round trips of the handshake.
+    round_trips: u32,
+    /// Size of the first flight of the client in bytes.
+    client_hello_size: usize,
+    /// Duration of loading the trusted root certificates.
+    root_certificates_load_duration: Duration,
+}
+
+impl TlsHandshakeTimings {
+    #[cfg_attr(not(feature = "rustls"), allow(dead_code))]
+    pub(crate) const fn new(
+        total: Duration,
+        server_hello_duration: Duration,
+        certificate_verification_duration: Duration,
+        round_trips: u32,
+        client_hello_size: usize,
+        root_certificates_load_duration: Duration,
+    ) -> Self {
+        Self {
+            total,
+            server_hello_duration,
+            certificate_verification_duration,
+            round_trips,
+            client_hello_size,
+            root_certificates_load_duration,
+        }
+    }
+
+    /// Returns the duration from sending the `ClientHello` until the first
+    /// bytes of the server's response (the `ServerHello`) were received. This
+    /// is roughly one network round trip plus the processing time of the
+    /// server.
+    #[must_use]
+    pub const fn server_hello_duration(&self) -> Duration {
+        self.server_hello_duration
+    }
+
+    /// Returns the time spent locally to verify the certificate chain of the
+    /// server and the handshake signatures. Zero for resumed sessions.
+    #[must_use]
+    pub const fn certificate_verification_duration(&self) -> Duration {
+        self.certificate_verification_duration
+    }
+
+    /// Returns the remaining duration of the handshake, i.e., the total
+    /// duration minus [`Self::server_hello_duration`] and
+    /// [`Self::certificate_verification_duration`]. This covers the transfer
+    /// of the remaining server flight (such as the certificates), further
+    /// round trips, and the cryptographic operations of the key exchange.
+    #[must_use]
+    pub const fn remaining_duration(&self) -> Duration {
+        self.total
+            .saturating_sub(self.server_hello_duration)
+            .saturating_sub(self.certificate_verification_duration)
+    }
+
+    /// Returns the number of network round trips of the handshake. For
+    /// example, this is one for a full TLS 1.3 handshake, and two for a full
+    /// TLS 1.2 handshake or a TLS 1.3 handshake with a `HelloRetryRequest`.
+    #[must_use]
+    pub const fn round_trips(&self) -> u32 {
+        self.round_trips
+    }
+
+    /// Returns the size in bytes of the first flight of the client, i.e., the
+    /// TLS records of the `ClientHello` and, if sent, the early data. Offering
+    /// post-quantum key shares increases this by more than 1 KiB, which may
+    /// no longer fit into a single TCP segment.
+    #[must_use]
+    pub const fn client_hello_size(&self) -> usize {
+        self.client_hello_size
+    }
+
+    /// Returns the duration of loading the trusted root certificates of the
+    /// system. This happens once before the measurement and is therefore not
+    /// part of the TLS handshake duration.
+    #[must_use]
+    pub const fn root_certificates_load_duration(&self) -> Duration {
+        self.root_certificates_load_duration
+    }
+}
+
+/// Measurements of a second connection to the same server that resumes the
+/// TLS session of the first connection.
+///
+/// All [`DurationPair`]s are relative to the begin of the second connection,
+/// i.e., they don't include a DNS lookup.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsResumption {
+    /// Relative duration of the TCP connection start.
+    tcp_connect_duration_rel: Duration,
+    /// Relative duration of the (resumed) TLS handshake.
+    tls_handshake_duration_rel: Duration,
+    /// The relative duration of the HTTP GET request sending. Zero, if the
+    /// request was sent as early data.
+    http_get_send_duration_rel: Duration,
+    /// The relative duration until the first byte from the HTTP response was
+    /// received. With early data, measured from the begin of the handshake.
+    http_ttfb_duration_rel: Duration,
+    /// Whether the server accepted the request as early data (0-RTT).
+    early_data_accepted: bool,
+    /// Negotiated TLS parameters of the second connection.
+    tls_info: TlsInfo,
+}
+
+impl TlsResumption {
+    pub(crate) const fn new(
+        tcp_connect_duration_rel: Duration,
+        tls_handshake_duration_rel: Duration,
+        http_get_send_duration_rel: Duration,
+        http_ttfb_duration_rel: Duration,
+        early_data_accepted: bool,
+        tls_info: TlsInfo,
+    ) -> Self {
+        Self {
+            tcp_connect_duration_rel,
+            tls_handshake_duration_rel,
+            http_get_send_duration_rel,
+            http_ttfb_duration_rel,
+            early_data_accepted,
+            tls_info,
+        }
+    }
+
+    /// Returns the [`DurationPair`] for the establishment of the TCP connection.
+    #[must_use]
+    pub fn tcp_connect_duration(&self) -> DurationPair {
+        DurationPair::new(self.tcp_connect_duration_rel, Duration::default())
+    }
+
+    /// Returns the [`DurationPair`] for the TLS handshake of the second
+    /// connection. Whether the session was actually resumed is reported by
+    /// [`TlsInfo::resumed`].
+    #[must_use]
+    pub fn tls_handshake_duration(&self) -> DurationPair {
+        let abs_dur_so_far = self.tcp_connect_duration().total();
+        DurationPair::new(self.tls_handshake_duration_rel, abs_dur_so_far)
+    }
+
+    /// Returns the [`DurationPair`] for the transmission of the HTTP GET
+    /// request. The relative duration is zero, if the request was sent as
+    /// early data.
+    #[must_use]
+    pub fn http_get_send_duration(&self) -> DurationPair {
+        let abs_dur_so_far = self.tls_handshake_duration().total();
+        DurationPair::new(self.http_get_send_duration_rel, abs_dur_so_far)
+    }
+
+    /// Returns the [`DurationPair`] for the time to first byte (TTFB) of the
+    /// HTTP response.
+    ///
+    /// If the request was sent as early data, it left with the first flight
+    /// of the handshake. The TTFB is then measured from the begin of the
+    /// handshake and overlaps with it.
+    #[must_use]
+    pub fn ttfb_duration(&self) -> DurationPair {
+        let abs_dur_so_far = if self.early_data_accepted {
+            self.tcp_connect_duration().total()
+        } else {
+            self.http_get_send_duration().total()
+        };
+        DurationPair::new(self.http_ttfb_duration_rel, abs_dur_so_far)
+    }
+
+    /// Whether the server accepted the HTTP request as TLS 1.3 early data
+    /// (0-RTT).
+    #[must_use]
+    pub const fn early_data_accepted(&self) -> bool {
+        self.early_data_accepted
+    }
+
+    /// Getter for the negotiated TLS parameters of the second connection.
+    #[must_use]
+    pub const fn tls_info(&self) -> &TlsInfo {
+        &self.tls_info
+    }
+}
+
+/// Formats a SAN entry. DNS names and IP addresses are printed as is, every
+/// other (rare) kind uses the representation of [`x509_parser`].
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+fn format_general_name(name: &GeneralName<'_>) -> String {
+    match name {
+        GeneralName::DNSName(name) => (*name).to_string(),
+        GeneralName::IPAddress(bytes) => match bytes.len() {
+            4 => IpAddr::from(<[u8; 4]>::try_from(*bytes).unwrap()).to_string(),
+            16 => IpAddr::from(<[u8; 16]>::try_from(*bytes).unwrap()).to_string(),
+            _ => name.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+fn unix_timestamp_to_system_time(timestamp: i64) -> SystemTime {
+    let offset = Duration::from_secs(timestamp.unsigned_abs());
+    if timestamp >= 0 {
+        SystemTime::UNIX_EPOCH + offset
+    } else {
+        SystemTime::UNIX_EPOCH - offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_handshake_timings_remaining_duration() {
+        let timings = TlsHandshakeTimings::new(
+            Duration::from_millis(10),
+            Duration::from_millis(6),
+            Duration::from_millis(3),
+            1,
+            512,
+            Duration::from_millis(20),
+        );
+        assert_eq!(timings.remaining_duration(), Duration::from_millis(1));
+
+        // Never underflows, even if the individual measurements are slightly
+        // off.
+        let timings = TlsHandshakeTimings::new(
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Duration::from_millis(1),
+            1,
+            512,
+            Duration::from_millis(20),
+        );
+        assert_eq!(timings.remaining_duration(), Duration::ZERO);
+    }
+}
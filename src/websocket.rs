@@ -0,0 +1,454 @@
+/*
+MIT License
+
+Copyright (c) 2024 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`WebSocketInfo`] and the measurements after the WebSocket
+//! upgrade (RFC 6455).
+
+use crate::response::header_values;
+use crate::{TtfbError, WebSocketOptions};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::io::{ErrorKind, Read, Write, sink};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// GUID that is appended to the key to compute `Sec-WebSocket-Accept`.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Payload of the ping frame, to recognize the matching pong.
+const PING_PAYLOAD: &[u8] = b"ttfb";
+
+/// Maximum payload length of control frames (close, ping, pong), as of
+/// RFC 6455.
+const MAX_CONTROL_PAYLOAD_LENGTH: u64 = 125;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// Result of the WebSocket upgrade of a `ws://` or `wss://` URL. The time to
+/// `101 Switching Protocols` is the
+/// [`TtfbOutcome::ttfb_duration`](crate::TtfbOutcome::ttfb_duration).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebSocketInfo {
+    /// Whether `Sec-WebSocket-Accept` matches the sent key.
+    accept_valid: bool,
+    /// The subprotocol that was selected by the server.
+    protocol: Option<String>,
+    /// Round trip of a ping and its pong, if requested.
+    ping_duration: Option<Duration>,
+    /// Duration between the upgrade and the first message of the server, if
+    /// requested.
+    first_message_duration: Option<Duration>,
+}
+
+impl WebSocketInfo {
+    /// Returns whether the `Sec-WebSocket-Accept` header of the server
+    /// matches the `Sec-WebSocket-Key` that was sent.
+    #[must_use]
+    pub const fn accept_valid(&self) -> bool {
+        self.accept_valid
+    }
+
+    /// Returns the subprotocol that was selected by the server via
+    /// `Sec-WebSocket-Protocol`, if any.
+    #[must_use]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Returns the round trip between sending a ping and receiving the
+    /// matching pong, if requested via [`WebSocketOptions::ping`]. `None`, if
+    /// no pong arrived within [`WebSocketOptions::timeout`].
+    #[must_use]
+    pub const fn ping_duration(&self) -> Option<Duration> {
+        self.ping_duration
+    }
+
+    /// Returns the duration between the upgrade and the first (text or
+    /// binary) message of the server, if requested via
+    /// [`WebSocketOptions::first_message`]. `None`, if no message arrived
+    /// within [`WebSocketOptions::timeout`].
+    #[must_use]
+    pub const fn first_message_duration(&self) -> Option<Duration> {
+        self.first_message_duration
+    }
+}
+
+/// Returns random bytes of the random number generator of the operating
+/// system, as RFC 6455 requires unpredictable keys and masks.
+fn random_bytes<const N: usize>() -> Result<[u8; N], TtfbError> {
+    let mut bytes = [0_u8; N];
+    getrandom::fill(&mut bytes).map_err(|err| TtfbError::OtherStreamError(err.into()))?;
+    Ok(bytes)
+}
+
+/// Generates a random `Sec-WebSocket-Key`.
+pub fn generate_key() -> Result<String, TtfbError> {
+    random_bytes::<16>().map(|bytes| BASE64.encode(bytes))
+}
+
+/// Computes the expected `Sec-WebSocket-Accept` for the key.
+fn accept_key(key: &str) -> String {
+    let mut sha1 = sha1_smol::Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(WEBSOCKET_GUID.as_bytes());
+    BASE64.encode(sha1.digest().bytes())
+}
+
+/// Checks the head of the `101 Switching Protocols` response and performs the
+/// measurements that were requested via [`WebSocketOptions`]. Pings of the
+/// server are answered meanwhile, so that it doesn't close the connection.
+/// Closes the WebSocket afterwards.
+pub fn measure_websocket(
+    tcp: &mut (impl Read + Write),
+    socket: &TcpStream,
+    key: &str,
+    head: &[u8],
+    options: &WebSocketOptions,
+) -> Result<WebSocketInfo, TtfbError> {
+    let upgrade = Instant::now();
+    let accept_valid = header_values(head, "Sec-WebSocket-Accept")
+        .first()
+        .is_some_and(|accept| *accept == accept_key(key));
+    let protocol = header_values(head, "Sec-WebSocket-Protocol")
+        .into_iter()
+        .next();
+
+    // The timeout limits the whole measurement, not every single read.
+    let deadline = options.timeout.map(|timeout| upgrade + timeout);
+    let mut ping_duration = None;
+    let mut first_message_duration = None;
+    let mut closed = false;
+    if options.ping {
+        let ping_start = Instant::now();
+        write_frame(tcp, OPCODE_PING, PING_PAYLOAD)?;
+        while let Some((opcode, payload)) = read_frame_until(tcp, socket, deadline)? {
+            match opcode {
+                OPCODE_PONG if payload == PING_PAYLOAD => {
+                    ping_duration = Some(ping_start.elapsed());
+                    break;
+                }
+                OPCODE_TEXT | OPCODE_BINARY => {
+                    first_message_duration.get_or_insert_with(|| upgrade.elapsed());
+                }
+                OPCODE_PING => write_frame(tcp, OPCODE_PONG, &payload)?,
+                OPCODE_CLOSE => {
+                    closed = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+    if options.first_message && first_message_duration.is_none() && !closed {
+        while let Some((opcode, payload)) = read_frame_until(tcp, socket, deadline)? {
+            match opcode {
+                OPCODE_TEXT | OPCODE_BINARY => {
+                    first_message_duration = Some(upgrade.elapsed());
+                    break;
+                }
+                OPCODE_PING => write_frame(tcp, OPCODE_PONG, &payload)?,
+                OPCODE_CLOSE => {
+                    closed = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+    if !closed {
+        // Normal closure. Errors are irrelevant, as the measurement is done.
+        let _ = write_frame(tcp, OPCODE_CLOSE, &1000_u16.to_be_bytes());
+    }
+    socket
+        .set_read_timeout(None)
+        .map_err(TtfbError::OtherStreamError)?;
+
+    Ok(WebSocketInfo {
+        accept_valid,
+        protocol,
+        ping_duration,
+        first_message_duration: first_message_duration.filter(|_| options.first_message),
+    })
+}
+
+/// Reads a single frame like [`read_frame`], but returns `None` once the
+/// deadline has passed.
+fn read_frame_until(
+    tcp: &mut impl Read,
+    socket: &TcpStream,
+    deadline: Option<Instant>,
+) -> Result<Option<(u8, Vec<u8>)>, TtfbError> {
+    if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        socket
+            .set_read_timeout(Some(remaining))
+            .map_err(TtfbError::OtherStreamError)?;
+    }
+    read_frame(tcp)
+}
+
+/// Writes a single, masked frame, as required for clients.
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> Result<(), TtfbError> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xffff => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    let mask = random_bytes::<4>()?;
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        payload
+            .iter()
+            .zip(mask.iter().cycle())
+            .map(|(byte, mask)| byte ^ mask),
+    );
+    writer
+        .write_all(&frame)
+        .and_then(|()| writer.flush())
+        .map_err(TtfbError::OtherStreamError)
+}
+
+/// Reads a single frame and returns its opcode and payload. Fragmented
+/// messages aren't reassembled, as only the arrival of their first frame is
+/// relevant. For the same reason, the payload of data frames is discarded
+/// and only the payload of control frames is returned. Returns `None` on a
+/// timeout or if the connection was closed.
+fn read_frame(reader: &mut impl Read) -> Result<Option<(u8, Vec<u8>)>, TtfbError> {
+    let mut header = [0_u8; 2];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(err)
+            if matches!(
+                err.kind(),
+                ErrorKind::WouldBlock
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::ConnectionReset
+            ) =>
+        {
+            return Ok(None);
+        }
+        Err(err) => return Err(TtfbError::OtherStreamError(err)),
+    }
+    let opcode = header[0] & 0x0f;
+    let masked = header[1] & 0x80 != 0;
+    let len = match header[1] & 0x7f {
+        126 => {
+            let mut len = [0_u8; 2];
+            reader
+                .read_exact(&mut len)
+                .map_err(TtfbError::OtherStreamError)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0_u8; 8];
+            reader
+                .read_exact(&mut len)
+                .map_err(TtfbError::OtherStreamError)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    let is_control = opcode & 0x8 != 0;
+    if is_control && len > MAX_CONTROL_PAYLOAD_LENGTH {
+        return Err(TtfbError::InvalidHttpResponse(format!(
+            "The WebSocket control frame has a payload of {len} bytes"
+        )));
+    }
+    let mut mask = [0_u8; 4];
+    if masked {
+        reader
+            .read_exact(&mut mask)
+            .map_err(TtfbError::OtherStreamError)?;
+    }
+    let mut payload = Vec::new();
+    let read = if is_control {
+        reader.take(len).read_to_end(&mut payload).map(|n| n as u64)
+    } else {
+        std::io::copy(&mut reader.take(len), &mut sink())
+    }
+    .map_err(TtfbError::OtherStreamError)?;
+    if read != len {
+        return Err(TtfbError::InvalidHttpResponse(
+            "The WebSocket connection was closed within a frame".to_string(),
+        ));
+    }
+    if masked {
+        payload
+            .iter_mut()
+            .zip(mask.iter().cycle())
+            .for_each(|(byte, mask)| *byte ^= mask);
+    }
+    Ok(Some((opcode, payload)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn accept_key_of_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn random_keys() {
+        let key = generate_key().unwrap();
+        assert_eq!(BASE64.decode(&key).unwrap().len(), 16);
+        assert_ne!(key, generate_key().unwrap());
+    }
+
+    #[test]
+    fn measure_websocket_answers_pings() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"\x89\x02hi").unwrap();
+            // The first message is only sent after the pong.
+            assert_eq!(
+                read_frame(&mut stream).unwrap(),
+                Some((OPCODE_PONG, b"hi".to_vec()))
+            );
+            stream.write_all(b"\x81\x02ok").unwrap();
+            assert_eq!(read_frame(&mut stream).unwrap().unwrap().0, OPCODE_CLOSE);
+        });
+        let options = WebSocketOptions {
+            first_message: true,
+            timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        let info = measure_websocket(&mut socket.try_clone().unwrap(), &socket, "", b"", &options)
+            .unwrap();
+        server.join().unwrap();
+        assert!(info.first_message_duration().is_some());
+    }
+
+    #[test]
+    fn frames_roundtrip() {
+        let mut frame = Vec::new();
+        write_frame(&mut frame, OPCODE_PONG, &[42; 100]).unwrap();
+        assert_eq!(frame[0], 0x8a);
+        assert_eq!(frame[1], 0x80 | 100, "masked");
+        assert_eq!(
+            read_frame(&mut frame.as_slice()).unwrap(),
+            Some((OPCODE_PONG, vec![42; 100]))
+        );
+
+        // The payload of data frames is drained, but not kept.
+        let mut frame = Vec::new();
+        write_frame(&mut frame, OPCODE_TEXT, &[42; 300]).unwrap();
+        write_frame(&mut frame, OPCODE_PING, b"next").unwrap();
+        assert_eq!(frame[1], 0x80 | 126, "masked with 16-bit length");
+        let mut reader = frame.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some((OPCODE_TEXT, Vec::new()))
+        );
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some((OPCODE_PING, b"next".to_vec()))
+        );
+
+        // Unmasked, fragmented text message with an interleaved ping.
+        let frames = b"\x01\x02he\x89\x02hi\x80\x03llo";
+        let mut reader = frames.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some((OPCODE_TEXT, Vec::new()))
+        );
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some((OPCODE_PING, b"hi".to_vec()))
+        );
+        assert_eq!(read_frame(&mut reader).unwrap(), Some((0x0, Vec::new())));
+    }
+
+    #[test]
+    fn read_frame_limits_payload() {
+        // A ping that claims a payload of 126 bytes.
+        let frame = b"\x89\x7e\x00\x7e";
+        assert_eq!(
+            read_frame(&mut frame.as_slice()),
+            Err(TtfbError::InvalidHttpResponse(
+                "The WebSocket control frame has a payload of 126 bytes".to_string()
+            ))
+        );
+        // A binary frame that claims 4 GiB, but the connection ends before.
+        let mut frame = b"\x82\x7f".to_vec();
+        frame.extend_from_slice(&(4_u64 << 30).to_be_bytes());
+        frame.extend_from_slice(&[0; 1024]);
+        assert_eq!(
+            read_frame(&mut frame.as_slice()),
+            Err(TtfbError::InvalidHttpResponse(
+                "The WebSocket connection was closed within a frame".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn measure_websocket_stops_at_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // Keeps sending pings and never a message, until the client is
+            // gone.
+            while stream.write_all(b"\x89\x00").is_ok() {
+                thread::sleep(Duration::from_millis(5));
+            }
+        });
+        let options = WebSocketOptions {
+            first_message: true,
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let info = measure_websocket(&mut socket.try_clone().unwrap(), &socket, "", b"", &options)
+            .unwrap();
+        assert!(now.elapsed() < Duration::from_secs(5));
+        assert_eq!(info.first_message_duration(), None);
+        drop(socket);
+        server.join().unwrap();
+    }
+}